@@ -0,0 +1,52 @@
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+
+/// instruction 하나가 실행되면서 접근하는 메모리 주소들.
+/// 간접 주소 지정(LDI/STI)의 경우 포인터를 읽는 것도 reads에 포함됩니다.
+#[derive(Default)]
+pub struct MemoryAccess {
+    pub reads: Vec<usize>,
+    pub write: Option<usize>,
+}
+
+/// 현재 PC의 instruction을 실행하기 전에, 그 instruction이 접근할 메모리 주소를 계산합니다.
+pub fn memory_access(vm: &VM) -> MemoryAccess {
+    let pc = vm.pc.wrapping_add(1);
+    let relative = |offset: i16| pc.wrapping_add(offset as u16) as usize;
+    let based = |base: i16, offset: i16| {
+        (vm.register[base as usize] as u16).wrapping_add(offset as u16) as usize
+    };
+    match Instruction::from_u16(vm.mem[vm.pc as usize]) {
+        Instruction::LD { offset, .. } => MemoryAccess {
+            reads: vec![relative(offset)],
+            write: None,
+        },
+        Instruction::LDI { offset, .. } => {
+            let ptr = relative(offset);
+            MemoryAccess {
+                reads: vec![ptr, vm.mem[ptr] as usize],
+                write: None,
+            }
+        }
+        Instruction::LDR { base, offset, .. } => MemoryAccess {
+            reads: vec![based(base, offset)],
+            write: None,
+        },
+        Instruction::ST { offset, .. } => MemoryAccess {
+            reads: vec![],
+            write: Some(relative(offset)),
+        },
+        Instruction::STI { offset, .. } => {
+            let ptr = relative(offset);
+            MemoryAccess {
+                reads: vec![ptr],
+                write: Some(vm.mem[ptr] as usize),
+            }
+        }
+        Instruction::STR { base, offset, .. } => MemoryAccess {
+            reads: vec![],
+            write: Some(based(base, offset)),
+        },
+        _ => Default::default(),
+    }
+}
@@ -0,0 +1,792 @@
+use crate::symbol::TableEntry;
+use lc3::vm::VM;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// 어셈블 중 발생한 오류. line은 1부터 시작하는 줄 번호입니다.
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}번째 줄: {}", self.line, self.message)
+    }
+}
+
+/// .ORIG ~ .END 사이의 연속된 코드 블록
+pub struct Section {
+    pub orig: u16,
+    pub words: Vec<u16>,
+}
+
+impl Section {
+    /// lc3as가 만드는 것과 같은 오브젝트 파일 형식(big-endian, 첫 word는 시작 주소)으로 변환합니다.
+    /// 결과는 `VM::load_u8`에 그대로 넘길 수 있습니다.
+    pub fn to_object(&self) -> Vec<u8> {
+        std::iter::once(self.orig)
+            .chain(self.words.iter().cloned())
+            .flat_map(|word| vec![(word >> 8) as u8, word as u8])
+            .collect()
+    }
+}
+
+/// 어셈블 결과
+pub struct Assembly {
+    pub sections: Vec<Section>,
+    /// (심볼 이름, 주소)
+    pub symbols: Vec<(String, u16)>,
+    /// (주소, 그 word를 만든 소스의 줄 번호)
+    pub lines: Vec<(u16, usize)>,
+    /// .FILL/.BLKW/.STRINGZ로 만든 데이터 word의 주소
+    pub data: Vec<u16>,
+    /// 그중 .BLKW로 자리만 잡아 둔(값을 정하지 않은) word의 주소
+    pub reserved: Vec<u16>,
+}
+
+impl Assembly {
+    /// 모든 section을 vm의 메모리에 불러오고, PC를 첫 section의 시작 주소로 둡니다.
+    /// `VM::load_u8`은 부를 때마다 PC를 바꾸므로, section마다 따로 부르면 마지막 section에서 시작하게 됩니다.
+    pub fn load(&self, vm: &mut VM) {
+        for section in self.sections.iter() {
+            vm.load_u8(&section.to_object());
+        }
+        if let Some(first) = self.sections.first() {
+            vm.pc = first.orig;
+        }
+    }
+
+    /// 심볼들을 syms에 저장합니다. `symbol::parse_symbol_table`과 같은 역할을 합니다.
+    pub fn fill_symbol_table(&self, syms: &mut [TableEntry]) {
+        for (name, addr) in self.symbols.iter() {
+            syms[*addr as usize] = TableEntry::Symbol(name.clone());
+        }
+    }
+}
+
+const TRAP_ALIASES: &[(&str, u16)] = &[
+    ("GETC", 0x20),
+    ("OUT", 0x21),
+    ("PUTS", 0x22),
+    ("IN", 0x23),
+    ("PUTSP", 0x24),
+    ("HALT", 0x25),
+];
+
+fn is_opcode(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    match upper.as_str() {
+        "ADD" | "AND" | "NOT" | "JMP" | "RET" | "JSR" | "JSRR" | "LD" | "LDI" | "LDR" | "LEA"
+        | "ST" | "STI" | "STR" | "RTI" | "TRAP" => true,
+        _ => {
+            branch_condition(&upper).is_some()
+                || TRAP_ALIASES.iter().any(|(alias, _)| *alias == upper)
+        }
+    }
+}
+
+/// BR, BRn, BRzp 등에서 nzp 비트를 얻습니다. 플래그가 없는 BR은 BRnzp와 같습니다.
+fn branch_condition(upper: &str) -> Option<u16> {
+    if !upper.starts_with("BR") {
+        return None;
+    }
+    let flags = &upper[2..];
+    if flags.is_empty() {
+        return Some(0b111);
+    }
+    let mut bits = 0;
+    let mut last = 3;
+    for c in flags.chars() {
+        let (bit, order) = match c {
+            'N' => (0b100, 0),
+            'Z' => (0b010, 1),
+            'P' => (0b001, 2),
+            _ => return None,
+        };
+        if last != 3 && order <= last {
+            return None;
+        }
+        last = order;
+        bits |= bit;
+    }
+    Some(bits)
+}
+
+fn is_directive(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// #10, #-3, x3000, 12 형태의 숫자를 파싱합니다.
+fn parse_number(token: &str) -> Option<i32> {
+    let (digits, radix) = if let Some(rest) = token.strip_prefix('#') {
+        (rest, 10)
+    } else if let Some(rest) = token.strip_prefix('x').or_else(|| token.strip_prefix('X')) {
+        (rest, 16)
+    } else {
+        (token, 10)
+    };
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, digits),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let n = i32::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -n } else { n })
+}
+
+fn parse_register(token: &str) -> Option<u16> {
+    let bytes = token.as_bytes();
+    if bytes.len() == 2
+        && (bytes[0] == b'R' || bytes[0] == b'r')
+        && (b'0'..=b'7').contains(&bytes[1])
+    {
+        Some(u16::from(bytes[1] - b'0'))
+    } else {
+        None
+    }
+}
+
+fn is_valid_label(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && parse_register(token).is_none()
+        && parse_number(token).is_none()
+}
+
+/// .STRINGZ의 문자열 리터럴을 해석합니다. 따옴표를 포함한 나머지 줄을 받습니다.
+fn parse_string(rest: &str) -> Result<Vec<u16>, String> {
+    let rest = rest.trim();
+    if !rest.starts_with('"') {
+        return Err(String::from(".STRINGZ에는 \"로 감싼 문자열이 필요합니다."));
+    }
+    let mut words = vec![];
+    let mut chars = rest[1..].chars();
+    loop {
+        let c = match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('e') => '\x1B',
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some(c) => return Err(format!("알 수 없는 escape 문자입니다: \\{}", c)),
+                None => return Err(String::from("문자열이 닫히지 않았습니다.")),
+            },
+            Some(c) if c.is_ascii() => c,
+            Some(c) => return Err(format!("ASCII가 아닌 문자는 쓸 수 없습니다: {}", c)),
+            None => return Err(String::from("문자열이 닫히지 않았습니다.")),
+        };
+        words.push(c as u16);
+    }
+    if !chars.as_str().trim().is_empty() {
+        return Err(String::from("문자열 뒤에 해석할 수 없는 내용이 있습니다."));
+    }
+    words.push(0);
+    Ok(words)
+}
+
+/// 주석(;)을 제외한 한 줄의 내용을 반환합니다. 문자열 안의 ;는 주석으로 보지 않습니다.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..idx],
+            _ => (),
+        }
+    }
+    line
+}
+
+/// 한 줄에서 레이블을 제외한 명령어 부분
+struct Statement {
+    line: usize,
+    addr: u16,
+    op: String,
+    /// 쉼표/공백으로 나눈 operand들
+    operands: Vec<String>,
+    /// op 뒤의 원래 문자열(.STRINGZ용)
+    rest: String,
+}
+
+struct Assembler {
+    statements: Vec<Statement>,
+    sections: Vec<(u16, usize)>,
+    labels: HashMap<String, u16>,
+    symbols: Vec<(String, u16)>,
+    errors: Vec<AsmError>,
+}
+
+impl Assembler {
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push(AsmError { line, message });
+    }
+
+    /// 1차 패스: 각 줄의 주소를 계산하고 레이블을 모읍니다.
+    fn first_pass(&mut self, source: &str) {
+        let mut location: Option<u32> = None;
+        // lc3as와 같이 .END 뒤의 내용은 다음 .ORIG가 나올 때까지 무시합니다.
+        let mut ended = false;
+        for (idx, raw) in source.lines().enumerate() {
+            let line = idx + 1;
+            let content = strip_comment(raw).trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            let (first, after_first) = split_token(content);
+            let (label, op, rest) = if is_opcode(first) || is_directive(first) {
+                (None, first, after_first)
+            } else {
+                let (op, rest) = split_token(after_first);
+                (Some(first), op, rest)
+            };
+
+            if op.eq_ignore_ascii_case(".ORIG") {
+                if location.is_some() {
+                    self.error(line, String::from(".END 없이 .ORIG가 다시 나왔습니다."));
+                }
+                match parse_number(rest.trim()) {
+                    Some(n) if (0..=0xFFFF).contains(&n) => {
+                        location = Some(n as u32);
+                        ended = false;
+                        self.sections.push((n as u16, self.statements.len()));
+                    }
+                    _ => self.error(line, format!("잘못된 .ORIG 주소입니다: {}", rest.trim())),
+                }
+                if let Some(label) = label {
+                    self.error(
+                        line,
+                        format!(".ORIG에는 레이블을 붙일 수 없습니다: {}", label),
+                    );
+                }
+                continue;
+            }
+
+            let addr = match location {
+                Some(addr) => addr,
+                None if ended => continue,
+                None => {
+                    self.error(line, String::from(".ORIG 밖에 있는 내용입니다."));
+                    continue;
+                }
+            };
+
+            if let Some(label) = label {
+                if !is_valid_label(label) {
+                    if op.is_empty() {
+                        self.error(line, format!("알 수 없는 명령어입니다: {}", label));
+                    } else {
+                        self.error(line, format!("잘못된 레이블입니다: {}", label));
+                    }
+                    continue;
+                }
+                if addr > 0xFFFF {
+                    self.error(line, String::from("메모리 범위를 벗어났습니다."));
+                } else if self.labels.contains_key(label) {
+                    self.error(line, format!("레이블이 중복 정의되었습니다: {}", label));
+                } else {
+                    self.labels.insert(label.to_owned(), addr as u16);
+                    self.symbols.push((label.to_owned(), addr as u16));
+                }
+            }
+
+            if op.is_empty() {
+                continue;
+            }
+            if op.eq_ignore_ascii_case(".END") {
+                location = None;
+                ended = true;
+                continue;
+            }
+
+            let operands = rest
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            let size = match op.to_ascii_uppercase().as_str() {
+                ".BLKW" => match operands.first().and_then(|n| parse_number(n)) {
+                    Some(n) if n > 0 => n as u32,
+                    _ => {
+                        self.error(line, String::from(".BLKW에는 양수 크기가 필요합니다."));
+                        continue;
+                    }
+                },
+                ".STRINGZ" => match parse_string(rest) {
+                    Ok(words) => words.len() as u32,
+                    Err(err) => {
+                        self.error(line, err);
+                        continue;
+                    }
+                },
+                ".FILL" => 1,
+                _ if is_directive(op) => {
+                    self.error(line, format!("알 수 없는 지시어입니다: {}", op));
+                    continue;
+                }
+                _ => 1,
+            };
+            if addr + size > 0x10000 {
+                self.error(line, String::from("메모리 범위를 벗어났습니다."));
+                location = Some(0x10000);
+                continue;
+            }
+            self.statements.push(Statement {
+                line,
+                addr: addr as u16,
+                op: op.to_owned(),
+                operands,
+                rest: rest.to_owned(),
+            });
+            location = Some(addr + size);
+        }
+        if location.is_some() {
+            self.error(source.lines().count(), String::from(".END가 없습니다."));
+        }
+    }
+
+    /// operand를 레이블 주소 또는 숫자로 해석합니다.
+    fn value(&self, operand: &str) -> Result<i32, String> {
+        if let Some(n) = parse_number(operand) {
+            return Ok(n);
+        }
+        match self.labels.get(operand) {
+            Some(addr) => Ok(i32::from(*addr)),
+            None => Err(format!("정의되지 않은 레이블입니다: {}", operand)),
+        }
+    }
+
+    /// PC-relative operand. 레이블이면 offset을 계산하고, 숫자면 그대로 offset으로 씁니다.
+    fn offset(&self, operand: &str, addr: u16, bits: u32) -> Result<u16, String> {
+        let offset = match parse_number(operand) {
+            Some(n) => n,
+            None => self.value(operand)? - (i32::from(addr) + 1),
+        };
+        signed_field(offset, bits)
+            .ok_or_else(|| format!("{}비트 offset 범위를 벗어났습니다: {}", bits, operand))
+    }
+
+    fn encode(&self, stmt: &Statement) -> Result<Vec<u16>, String> {
+        let ops = &stmt.operands;
+        let expect = |n: usize| {
+            if ops.len() == n {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{}에는 operand가 {}개 필요합니다. ({}개 주어짐)",
+                    stmt.op,
+                    n,
+                    ops.len()
+                ))
+            }
+        };
+        let reg = |idx: usize| {
+            parse_register(&ops[idx]).ok_or_else(|| format!("레지스터가 필요합니다: {}", ops[idx]))
+        };
+
+        let upper = stmt.op.to_ascii_uppercase();
+        let word = match upper.as_str() {
+            ".FILL" => {
+                expect(1)?;
+                let n = self.value(&ops[0])?;
+                if !(-0x8000..=0xFFFF).contains(&n) {
+                    return Err(format!("16비트 범위를 벗어나는 값입니다: {}", ops[0]));
+                }
+                n as u16
+            }
+            ".BLKW" => {
+                let n = parse_number(&ops[0]).unwrap_or(0) as usize;
+                return Ok(vec![0; n]);
+            }
+            ".STRINGZ" => return parse_string(&stmt.rest),
+            "ADD" | "AND" => {
+                expect(3)?;
+                let opcode = if upper == "ADD" { 0x1000 } else { 0x5000 };
+                let base = opcode | reg(0)? << 9 | reg(1)? << 6;
+                match parse_register(&ops[2]) {
+                    Some(src2) => base | src2,
+                    None => {
+                        let immd = parse_number(&ops[2])
+                            .ok_or_else(|| format!("레지스터나 숫자가 필요합니다: {}", ops[2]))?;
+                        base | 1 << 5
+                            | signed_field(immd, 5).ok_or_else(|| {
+                                format!("5비트 immediate 범위를 벗어났습니다: {}", ops[2])
+                            })?
+                    }
+                }
+            }
+            "NOT" => {
+                expect(2)?;
+                0x9000 | reg(0)? << 9 | reg(1)? << 6 | 0x3F
+            }
+            "JMP" => {
+                expect(1)?;
+                0xC000 | reg(0)? << 6
+            }
+            "RET" => {
+                expect(0)?;
+                0xC1C0
+            }
+            "JSRR" => {
+                expect(1)?;
+                0x4000 | reg(0)? << 6
+            }
+            "JSR" => {
+                expect(1)?;
+                0x4800 | self.offset(&ops[0], stmt.addr, 11)?
+            }
+            "LD" | "LDI" | "LEA" | "ST" | "STI" => {
+                expect(2)?;
+                let opcode = match upper.as_str() {
+                    "LD" => 0x2000,
+                    "LDI" => 0xA000,
+                    "LEA" => 0xE000,
+                    "ST" => 0x3000,
+                    _ => 0xB000,
+                };
+                opcode | reg(0)? << 9 | self.offset(&ops[1], stmt.addr, 9)?
+            }
+            "LDR" | "STR" => {
+                expect(3)?;
+                let opcode = if upper == "LDR" { 0x6000 } else { 0x7000 };
+                let offset = parse_number(&ops[2])
+                    .ok_or_else(|| format!("숫자가 필요합니다: {}", ops[2]))?;
+                opcode
+                    | reg(0)? << 9
+                    | reg(1)? << 6
+                    | signed_field(offset, 6)
+                        .ok_or_else(|| format!("6비트 offset 범위를 벗어났습니다: {}", ops[2]))?
+            }
+            "RTI" => {
+                expect(0)?;
+                0x8000
+            }
+            "TRAP" => {
+                expect(1)?;
+                match parse_number(&ops[0]) {
+                    Some(n) if (0..=0xFF).contains(&n) => 0xF000 | n as u16,
+                    _ => return Err(format!("잘못된 trap vector입니다: {}", ops[0])),
+                }
+            }
+            _ => {
+                if let Some((_, vect)) = TRAP_ALIASES.iter().find(|(alias, _)| *alias == upper) {
+                    expect(0)?;
+                    0xF000 | vect
+                } else if let Some(cond) = branch_condition(&upper) {
+                    expect(1)?;
+                    cond << 9 | self.offset(&ops[0], stmt.addr, 9)?
+                } else {
+                    return Err(format!("알 수 없는 명령어입니다: {}", stmt.op));
+                }
+            }
+        };
+        Ok(vec![word])
+    }
+}
+
+/// 첫 토큰과 나머지 문자열로 나눕니다.
+fn split_token(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(|c: char| c.is_whitespace() || c == ',') {
+        Some(idx) => (
+            &s[..idx],
+            s[idx..].trim_start_matches(|c: char| c == ',' || c.is_whitespace()),
+        ),
+        None => (s, ""),
+    }
+}
+
+/// value를 bits 비트의 2의 보수 필드로 만듭니다. 범위를 벗어나면 None입니다.
+fn signed_field(value: i32, bits: u32) -> Option<u16> {
+    let min = -(1 << (bits - 1));
+    let max = (1 << (bits - 1)) - 1;
+    if value < min || value > max {
+        None
+    } else {
+        Some((value as u16) & ((1 << bits) - 1))
+    }
+}
+
+/// LC-3 어셈블리 소스를 어셈블합니다. 오류가 있다면 모든 오류를 줄 번호와 함께 반환합니다.
+pub fn assemble(source: &str) -> Result<Assembly, Vec<AsmError>> {
+    let mut asm = Assembler {
+        statements: vec![],
+        sections: vec![],
+        labels: HashMap::new(),
+        symbols: vec![],
+        errors: vec![],
+    };
+    asm.first_pass(source);
+
+    let mut sections = asm
+        .sections
+        .iter()
+        .map(|&(orig, _)| Section {
+            orig,
+            words: vec![],
+        })
+        .collect::<Vec<_>>();
+    let mut errors = vec![];
+    let mut lines = vec![];
+    let mut data = vec![];
+    let mut reserved = vec![];
+    for (idx, stmt) in asm.statements.iter().enumerate() {
+        let section = asm
+            .sections
+            .iter()
+            .rposition(|&(_, first)| first <= idx)
+            .unwrap();
+        match asm.encode(stmt) {
+            Ok(words) => {
+                lines.extend((0..words.len()).map(|offset| (stmt.addr + offset as u16, stmt.line)));
+                let addrs = (0..words.len()).map(|offset| stmt.addr + offset as u16);
+                if stmt.op.eq_ignore_ascii_case(".BLKW") {
+                    reserved.extend(addrs.clone());
+                }
+                if is_directive(&stmt.op) {
+                    data.extend(addrs);
+                }
+                sections[section].words.extend(words);
+            }
+            Err(message) => errors.push(AsmError {
+                line: stmt.line,
+                message,
+            }),
+        }
+    }
+
+    asm.errors.extend(errors);
+    if asm.errors.is_empty() {
+        Ok(Assembly {
+            sections: sections
+                .into_iter()
+                .filter(|s| !s.words.is_empty())
+                .collect(),
+            symbols: asm.symbols,
+            lines,
+            data,
+            reserved,
+        })
+    } else {
+        asm.errors.sort_by_key(|err| err.line);
+        Err(asm.errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// x3000에서 시작하는 프로그램 하나를 어셈블해 word들을 반환합니다.
+    fn words(body: &str) -> Vec<u16> {
+        let source = format!(".ORIG x3000\n{}\n.END\n", body);
+        match assemble(&source) {
+            Ok(assembly) => {
+                assert_eq!(assembly.sections.len(), 1);
+                assembly.sections.into_iter().next().unwrap().words
+            }
+            Err(errors) => panic!(
+                "어셈블하지 못했습니다: {}",
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// 어셈블에 실패해야 하는 소스의 (줄 번호, 메시지)들을 반환합니다.
+    fn errors(source: &str) -> Vec<(usize, String)> {
+        match assemble(source) {
+            Ok(_) => panic!("오류 없이 어셈블되었습니다: {}", source),
+            Err(errors) => errors.into_iter().map(|e| (e.line, e.message)).collect(),
+        }
+    }
+
+    #[test]
+    fn opcode_encodings() {
+        let cases: &[(&str, u16)] = &[
+            ("ADD R1, R2, R3", 0x1283),
+            ("ADD R1, R2, #-1", 0x12BF),
+            ("add r7, r7, x0F", 0x1FEF),
+            ("AND R0, R0, #0", 0x5020),
+            ("AND R3, R4, R5", 0x5705),
+            ("NOT R2, R3", 0x94FF),
+            ("JMP R2", 0xC080),
+            ("RET", 0xC1C0),
+            ("JSRR R3", 0x40C0),
+            ("JSR #-1", 0x4FFF),
+            ("LD R0, #2", 0x2002),
+            ("LDI R1, #-1", 0xA3FF),
+            ("LEA R2, #0", 0xE400),
+            ("ST R3, #5", 0x3605),
+            ("STI R4, #-256", 0xB900),
+            ("LDR R5, R6, #-32", 0x6BA0),
+            ("STR R7, R0, #31", 0x7E1F),
+            ("RTI", 0x8000),
+            ("TRAP x23", 0xF023),
+            ("GETC", 0xF020),
+            ("OUT", 0xF021),
+            ("PUTS", 0xF022),
+            ("IN", 0xF023),
+            ("PUTSP", 0xF024),
+            ("HALT", 0xF025),
+            ("BR #1", 0x0E01),
+            ("BRnzp #-1", 0x0FFF),
+            ("BRz #0", 0x0400),
+            ("BRnp #3", 0x0A03),
+            ("brn #255", 0x08FF),
+            ("BRzp #-256", 0x0700),
+        ];
+        for &(line, expected) in cases {
+            assert_eq!(words(line), vec![expected], "{}", line);
+        }
+    }
+
+    #[test]
+    fn label_offsets() {
+        let source = "
+LOOP    ADD R0, R0, #-1
+        BRp LOOP
+        JSR SUB
+        LD R1, DATA
+        HALT
+SUB     RET
+DATA    .FILL x1234";
+        assert_eq!(
+            words(source),
+            vec![0x103F, 0x03FE, 0x4802, 0x2202, 0xF025, 0xC1C0, 0x1234]
+        );
+    }
+
+    #[test]
+    fn directives() {
+        assert_eq!(words(".FILL #-1"), vec![0xFFFF]);
+        assert_eq!(words(".FILL xFFFF"), vec![0xFFFF]);
+        assert_eq!(words(".BLKW 3"), vec![0, 0, 0]);
+
+        let assembly = assemble(".ORIG x3000\nHALT\nA .BLKW 2\nB .FILL 5\n.END")
+            .ok()
+            .unwrap();
+        assert_eq!(assembly.data, vec![0x3001, 0x3002, 0x3003]);
+        assert_eq!(assembly.reserved, vec![0x3001, 0x3002]);
+        assert_eq!(
+            assembly.symbols,
+            vec![("A".to_owned(), 0x3001), ("B".to_owned(), 0x3003)]
+        );
+    }
+
+    #[test]
+    fn stringz_escapes() {
+        let expected = "a\n\t\r\x1B\"\\;b\0"
+            .chars()
+            .map(|c| c as u16)
+            .collect::<Vec<_>>();
+        assert_eq!(words(r#".STRINGZ "a\n\t\r\e\"\\;b" ; comment"#), expected);
+        assert_eq!(words(r#".STRINGZ "\0""#), vec![0, 0]);
+        assert_eq!(words(r#".STRINGZ """#), vec![0]);
+
+        let errors = errors(".ORIG x3000\n.STRINGZ \"\\q\"\n.STRINGZ \"open\n.END");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, 2);
+        assert!(errors[0].1.contains("escape"));
+        assert_eq!(errors[1].0, 3);
+    }
+
+    #[test]
+    fn out_of_range_offsets() {
+        let errors = errors(
+            ".ORIG x3000
+        BRnzp FAR
+        ADD R0, R0, #16
+        LDR R0, R1, #32
+        JSR #1024
+        .BLKW 300
+FAR     HALT
+        .END",
+        );
+        let lines = errors.iter().map(|e| e.0).collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 3, 4, 5]);
+        assert!(errors[0].1.contains("9비트 offset"));
+        assert!(errors[1].1.contains("5비트 immediate"));
+        assert!(errors[2].1.contains("6비트 offset"));
+        assert!(errors[3].1.contains("11비트 offset"));
+    }
+
+    #[test]
+    fn undefined_labels() {
+        let errors = errors(".ORIG x3000\nLD R0, NOWHERE\n.FILL ALSO_NOWHERE\nHALT\n.END");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, 2);
+        assert!(errors[0].1.contains("NOWHERE"));
+        assert_eq!(errors[1].0, 3);
+        assert!(errors[1].1.contains("ALSO_NOWHERE"));
+    }
+
+    #[test]
+    fn to_object_matches_lc3as() {
+        // lc3as로 어셈블한 다음 프로그램의 .obj 파일과 같은 내용입니다.
+        let assembly = assemble(
+            ".ORIG x3000
+        AND R0, R0, #0
+        ADD R0, R0, #5
+        LEA R0, MSG
+        PUTS
+        HALT
+MSG     .STRINGZ \"Hi\"
+        .END",
+        )
+        .ok()
+        .unwrap();
+        let object = assembly.sections[0].to_object();
+        assert_eq!(
+            object,
+            vec![
+                0x30, 0x00, 0x50, 0x20, 0x10, 0x25, 0xE0, 0x02, 0xF0, 0x22, 0xF0, 0x25, 0x00, 0x48,
+                0x00, 0x69, 0x00, 0x00,
+            ]
+        );
+
+        let mut vm = lc3::vm::VM::new();
+        vm.load_u8(&object);
+        assert_eq!(vm.pc, 0x3000);
+        assert_eq!(vm.mem[0x3002], 0xE002);
+        assert_eq!(vm.mem[0x3007], 0x0000);
+    }
+
+    #[test]
+    fn load_starts_at_the_first_section() {
+        let assembly = assemble(
+            ".ORIG x3000
+        HALT
+        .END
+        .ORIG x4000
+        DATA .FILL x1234
+        .END",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(assembly.sections.len(), 2);
+
+        let mut vm = VM::new();
+        assembly.load(&mut vm);
+        assert_eq!(vm.pc, 0x3000);
+        assert_eq!(vm.mem[0x3000], 0xF025);
+        assert_eq!(vm.mem[0x4000], 0x1234);
+    }
+}
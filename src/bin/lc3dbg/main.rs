@@ -5,104 +5,573 @@ use lc3::vm::{MCR, VM};
 use pretty_bytes::converter::convert;
 use std::env::args;
 use std::fs;
-use std::io::{ErrorKind, Result as IOResult};
+use std::io::{stdin, BufRead, BufReader, ErrorKind, IsTerminal, Result as IOResult};
 use std::path::Path;
+use std::process;
+use breakpoint::BreakpointSet;
 use undo::UndoBuffer;
 
 const HELP: &str = r#"lc3dbg - LC-3 디버거
 사용법:
     lc3dbg <filename1>, <filename2>, ...: 주어진 LC-3 오브젝트 파일들을 순서대로 엽니다.
+        .asm 파일은 내장 어셈블러로 어셈블한 후 엽니다. (심볼 테이블도 함께 만들어집니다.)
+    lc3dbg --script <cmds.txt> <filename1>, ...: 명령어 파일의 명령어들을 차례로 실행한 후 종료합니다.
+        표준 입력이 터미널이 아니라면(파이프 등), 표준 입력에서 명령어를 읽어 같은 방식으로 실행합니다.
+    lc3dbg --exit-on-error ...: 스크립트 모드에서 명령어가 실패하면 곧바로 종료합니다.
+    lc3dbg --input <file> ...: 파일의 내용을 프로그램에 전달할 입력(input)으로 미리 설정합니다.
+    lc3dbg --snapshot <file> ...: 프로그램들을 연 후, snapshot save로 저장한 상태를 불러와 시작합니다.
+        --input이 함께 주어지면 스냅샷의 입력 대신 --input의 내용을 씁니다.
+    lc3dbg --coverage <file> ...: 처음부터 커버리지를 기록하고, 종료할 때 파일에 저장합니다.
+        파일이 이미 있다면 그 기록에 이어서 기록하므로, 여러 번의 실행(test 포함)의 커버리지를 모을 수 있습니다.
+    lc3dbg --no-fault ...: 실행 오류 검사를 끈 채로 시작합니다. (fault off와 같습니다.)
+    lc3dbg test <spec.toml> <filename1>, ...: 테스트 명세의 각 케이스를 새로 불러온 VM에서 실행하고
+        결과를 출력합니다. 모든 케이스가 통과하면 0, 아니면 1을 종료 코드로 반환합니다.
+        실행 오류가 나면 케이스는 실패합니다. lc3dbg test <spec.toml> --no-fault ...로 검사를 끌 수 있습니다.
     lc3dbg --help: 이 도움말을 출력합니다.
+
+스크립트 모드의 종료 코드:
+    0: VM이 정상적으로 멈췄습니다(Clock Enable Bit이 0입니다).
+    1: 명령어를 모두 실행했지만 VM이 아직 켜져 있습니다.
+    2: --exit-on-error가 주어졌고, 명령어가 실패했습니다.
 "#;
 
+/// 스크립트 모드에서 명령어가 실패해 종료할 때의 종료 코드
+const EXIT_COMMAND_FAILED: i32 = 2;
+
 fn print_help(term: &Term) -> IOResult<()> {
     term.write_line(HELP)
 }
 
+/// 프롬프트에서 Tab으로 완성할 명령어 이름
+const COMMANDS: &[&str] = &[
+    "backtrace",
+    "break",
+    "breakpoints",
+    "buffer",
+    "bye",
+    "checkpoint",
+    "condition",
+    "coverage",
+    "delete",
+    "disable",
+    "enable",
+    "fault",
+    "finish",
+    "help",
+    "ignore",
+    "input",
+    "loopcheck",
+    "mem",
+    "next",
+    "noinput",
+    "output",
+    "profile",
+    "redo",
+    "restore",
+    "reverse-continue",
+    "reverse-finish",
+    "reverse-step",
+    "run",
+    "set",
+    "snapshot",
+    "stat",
+    "step",
+    "sym",
+    "trace",
+    "turn",
+    "undo",
+    "uninit",
+    "watch",
+];
+
+/// 프롬프트에서 빈 줄을 입력하면 다시 실행하는 명령어.
+/// 실수로 누른 Enter로 프로그램 전체를 실행하거나 기록을 되돌리지 않도록, 한 단계씩 실행하는 명령어만 둡니다.
+const REPEATABLE: &[&str] = &["step", "s", "next", "n", "finish", "stat"];
+
+/// 디버거 명령어를 읽어올 곳
+enum CommandSource {
+    Terminal(lineedit::LineEditor),
+    /// 스크립트 파일 또는 파이프로 연결된 표준 입력
+    Script(Box<dyn BufRead>),
+}
+
+impl CommandSource {
+    fn is_interactive(&self) -> bool {
+        matches!(self, CommandSource::Terminal(_))
+    }
+
+    /// 다음 명령어를 읽습니다. 빈 줄과 #으로 시작하는 주석은 건너뛰며, 더 읽을 명령어가 없으면 None을 반환합니다.
+    /// 스크립트에서 읽은 명령어는 실행 기록을 남기기 위해 프롬프트와 함께 출력합니다.
+    /// 터미널에서 빈 줄을 입력하면 마지막 명령어가 step 등 REPEATABLE이라면 다시 실행합니다.
+    fn read_command(
+        &mut self,
+        term: &Term,
+        symbol_table: &[symbol::TableEntry],
+    ) -> IOResult<Option<String>> {
+        match self {
+            CommandSource::Terminal(editor) => loop {
+                let line = match editor.read_line(term, "lc3dbg>", symbol_table)? {
+                    Some(line) => line.trim().to_owned(),
+                    None => return Ok(None),
+                };
+                if !line.is_empty() {
+                    editor.add_history(&line);
+                    return Ok(Some(line));
+                }
+                if let Some(last) = editor.last_command() {
+                    let cmd = last.split(' ').next().unwrap_or("");
+                    if REPEATABLE.contains(&cmd) {
+                        return Ok(Some(last.to_owned()));
+                    }
+                }
+            },
+            CommandSource::Script(reader) => loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                term.write_line(&format!("lc3dbg>{}", line))?;
+                return Ok(Some(line.to_owned()));
+            },
+        }
+    }
+}
+
 fn main() -> Result<(), Error> {
-    let args = args().skip(1).collect::<Vec<_>>();
     let mut term = Term::stdout();
     term.write_line(&format!("lc3dbg v{}", env!("CARGO_PKG_VERSION")))?;
 
-    if args == ["--help".to_owned()] {
-        print_help(&term)?;
-        return Ok(());
+    let mut files = Vec::new();
+    let mut script = None;
+    let mut exit_on_error = false;
+    let mut input_file = None;
+    let mut snapshot_file = None;
+    let mut coverage_file = None;
+    let mut no_fault = false;
+    let mut test_spec = None;
+    let mut arg_iter = args().skip(1).peekable();
+    if arg_iter.peek().map(String::as_str) == Some("test") {
+        arg_iter.next();
+        match arg_iter.next() {
+            Some(spec) => test_spec = Some(spec),
+            None => {
+                term.write_line("사용법: lc3dbg test <spec.toml> <filename1>, ...")?;
+                process::exit(EXIT_COMMAND_FAILED);
+            }
+        }
+    }
+    while let Some(arg) = arg_iter.next() {
+        match arg.as_str() {
+            "--help" => {
+                print_help(&term)?;
+                return Ok(());
+            }
+            "--script" => match arg_iter.next() {
+                Some(path) => script = Some(path),
+                None => {
+                    term.write_line("--script 뒤에 명령어 파일을 주어야 합니다.")?;
+                    process::exit(EXIT_COMMAND_FAILED);
+                }
+            },
+            "--exit-on-error" => exit_on_error = true,
+            "--no-fault" => no_fault = true,
+            "--input" => match arg_iter.next() {
+                Some(path) => input_file = Some(path),
+                None => {
+                    term.write_line("--input 뒤에 입력 파일을 주어야 합니다.")?;
+                    process::exit(EXIT_COMMAND_FAILED);
+                }
+            },
+            "--snapshot" => match arg_iter.next() {
+                Some(path) => snapshot_file = Some(path),
+                None => {
+                    term.write_line("--snapshot 뒤에 스냅샷 파일을 주어야 합니다.")?;
+                    process::exit(EXIT_COMMAND_FAILED);
+                }
+            },
+            "--coverage" => match arg_iter.next() {
+                Some(path) => coverage_file = Some(path),
+                None => {
+                    term.write_line("--coverage 뒤에 커버리지 파일을 주어야 합니다.")?;
+                    process::exit(EXIT_COMMAND_FAILED);
+                }
+            },
+            _ => files.push(arg),
+        }
     }
 
+    let mut commands = match script {
+        Some(path) => match fs::File::open(&path) {
+            Ok(file) => CommandSource::Script(Box::new(BufReader::new(file))),
+            Err(err) => {
+                term.write_line(&format!("명령어 파일을 열 수 없습니다: {}: {}", path, err))?;
+                process::exit(EXIT_COMMAND_FAILED);
+            }
+        },
+        None if !stdin().is_terminal() => CommandSource::Script(Box::new(stdin().lock())),
+        None => CommandSource::Terminal(lineedit::LineEditor::new(COMMANDS)),
+    };
+    let interactive = test_spec.is_none() && commands.is_interactive();
+
     let mut vm = VM::new();
     let mut symbol_table = vec![symbol::TableEntry::Unknown; 65536];
+    let mut source_map = source::SourceMap::new();
+    let mut faults = fault::FaultDetector::new();
+    faults.enabled = !no_fault;
+    let mut uninit = uninit::UninitTracker::new();
+    let mut coverage = coverage::Coverage::new();
+    coverage.enabled = coverage_file.is_some();
 
-    for arg in args.into_iter() {
-        term.write_line(&format!("프로그램 로드: {}", arg))?;
+    for arg in files.into_iter() {
+        if interactive {
+            term.write_line(&format!("프로그램 로드: {}", arg))?;
+        }
         let path = Path::new(&arg);
-        vm.load_file(path)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("asm"))
+        {
+            let source = fs::read_to_string(path)?;
+            match asm::assemble(&source) {
+                Ok(assembly) => {
+                    assembly.load(&mut vm);
+                    assembly.fill_symbol_table(&mut symbol_table);
+                    source_map.add_file(&arg, &source, &assembly.lines);
+                    faults.load_assembly(&assembly);
+                    uninit.load_assembly(&assembly);
+                    coverage.load_assembly(&assembly);
+                    if interactive {
+                        term.move_cursor_up(1)?;
+                        term.clear_line()?;
+                    }
+                    term.write_line(&format!("프로그램 로드: {}(어셈블됨)", arg))?;
+                }
+                Err(errors) => {
+                    for err in errors.iter() {
+                        term.write_line(&format!("{}: {}", arg, err))?;
+                    }
+                    term.write_line(&format!("어셈블에 실패했습니다: {}", arg))?;
+                    process::exit(EXIT_COMMAND_FAILED);
+                }
+            }
+            continue;
+        }
+        let object = fs::read(path)?;
+        vm.load_u8(&object);
+        faults.load_object(&object);
+        uninit.load_object(&object);
+        coverage.load_object(&object);
+        let mut extras = String::new();
         match fs::read(path.with_extension("sym")) {
-            Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
-            file @ _ => {
-                term.move_cursor_up(1)?;
-                term.clear_line()?;
-                term.write_line(&format!("프로그램 로드: {}+sym", arg))?;
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            file => {
+                extras += "+sym";
                 symbol::parse_symbol_table(&String::from_utf8(file?)?, &mut symbol_table)
             }
         };
+        // 같은 이름의 .asm 파일이 있고 어셈블 결과가 오브젝트 파일과 같다면 소스 정보로 사용합니다.
+        let asm_path = path.with_extension("asm");
+        if let Ok(source) = fs::read_to_string(&asm_path) {
+            if let Ok(assembly) = asm::assemble(&source) {
+                if assembly.sections.iter().all(|section| {
+                    let orig = section.orig as usize;
+                    vm.mem[orig..orig + section.words.len()] == section.words[..]
+                }) {
+                    extras += "+asm";
+                    source_map.add_file(&asm_path.to_string_lossy(), &source, &assembly.lines);
+                    faults.load_assembly(&assembly);
+                    uninit.load_assembly(&assembly);
+                    coverage.load_assembly(&assembly);
+                }
+            }
+        }
+        if !interactive {
+            term.write_line(&format!("프로그램 로드: {}{}", arg, extras))?;
+        } else if !extras.is_empty() {
+            term.move_cursor_up(1)?;
+            term.clear_line()?;
+            term.write_line(&format!("프로그램 로드: {}{}", arg, extras))?;
+        }
     }
 
     symbol::symbol_table_postprocess(&mut symbol_table);
+    // 스크립트 모드의 --exit-on-error는 직전 명령어가 실패했다면 종료합니다.
+    let mut status = helper::Status::Done;
+    if let Some(path) = &coverage_file {
+        status = coverage.merge_if_exists(path, &term)?;
+    }
 
-    let mut pre_input: Vec<u8> = vec![];
+    let mut snapshot_input = vec![];
+    if let Some(path) = snapshot_file {
+        match snapshot::load(&path) {
+            Ok(snapshot) => {
+                vm = snapshot.vm.clone();
+                snapshot.fill_symbol_table(&mut symbol_table);
+                snapshot_input = snapshot.pre_input;
+                faults.forget_images();
+                uninit.define_all();
+                term.write_line(&format!("스냅샷을 불러왔습니다: {}", path))?;
+            }
+            Err(err) => {
+                term.write_line(&format!("스냅샷을 불러오지 못했습니다: {}", err))?;
+                process::exit(EXIT_COMMAND_FAILED);
+            }
+        }
+    }
+
+    if let Some(spec) = test_spec {
+        let passed =
+            harness::test_command(&vm, &faults, &mut coverage, &symbol_table, &term, &spec)?;
+        if let Some(path) = coverage_file {
+            // 종료 코드는 테스트 결과로 정하므로, 저장하지 못했다면 알리기만 합니다.
+            let _ = coverage.save_and_report(&vm, &path, &term)?;
+        }
+        process::exit(if passed { 0 } else { 1 });
+    }
+
+    let mut pre_input: Vec<u8> = match input_file {
+        Some(path) => match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                term.write_line(&format!("입력 파일을 읽을 수 없습니다: {}: {}", path, err))?;
+                process::exit(EXIT_COMMAND_FAILED);
+            }
+        },
+        None => snapshot_input,
+    };
     let mut undo_buffer: Option<UndoBuffer> = None;
+    let mut breakpoints = BreakpointSet::new();
+    let mut checkpoints = checkpoint::CheckpointSet::new();
+    let mut tracer: Option<trace::Tracer> = None;
+    let mut call_stack = callstack::CallStack::new();
+    let mut profiler = profile::Profiler::new();
+    let mut loop_detector = loopcheck::LoopDetector::new();
+    // 프로그램을 불러온 뒤 실행한 instruction의 수. undo 등으로 되돌리면 줄어듭니다.
+    let mut executed = 0usize;
+    // 마지막 실행이 watchpoint에 걸려 멈췄고 그 뒤로 VM이 바뀌지 않았는지. reverse-continue가 그 watchpoint를 건너뜁니다.
+    let mut at_watch_stop = false;
+    let mut output = output::OutputBuffer::new(true);
 
+    if interactive {
+        interrupt::install_handler();
+    }
     helper::print_register_status(&vm, &term)?;
+    source_map.print_context(vm.pc as usize, &term)?;
 
     'cmdloop: loop {
-        term.write_str("lc3dbg>")?;
-        let line = term.read_line()?.trim().to_owned();
+        if status == helper::Status::Failed && !interactive && exit_on_error {
+            term.write_line("명령어가 실패해 종료합니다.")?;
+            process::exit(EXIT_COMMAND_FAILED);
+        }
+        status = helper::Status::Done;
+        let line = match commands.read_command(&term, &symbol_table)? {
+            Some(line) => line,
+            None => break,
+        };
         let (cmd, body) = {
             let mut it = line.splitn(2, ' ');
             (it.next(), it.next())
         };
 
-        match cmd {
-            Some("help") => help_command(&mut vm, &term, body),
+        // VM을 바꾸는 명령어를 실행하면 더 이상 watchpoint에 걸린 자리가 아닙니다.
+        if let Some("set") | Some("undo") | Some("redo") | Some("restore") | Some("snapshot")
+        | Some("turn") = cmd
+        {
+            at_watch_stop = false;
+        }
+        status = match cmd {
+            Some("help") => help_command(&mut vm, &term, body).map(|_| helper::Status::Done),
             Some("bye") => {
                 break;
             }
-            Some("run") => {
+            Some("run") | Some("step") | Some("s") | Some("next") | Some("n")
+            | Some("finish") => {
                 if vm.mem[MCR] >> 15 == 0 {
-                    term.write_line("VM이 꺼져 있습니다.")?;
+                    status = helper::report_error(&term, "VM이 꺼져 있습니다.")?;
                     term.write_line(
                         "강제로 켜려면, `turn on` 명령어를 사용하세요.",
                     )?;
                     continue;
                 }
-                let instructions = run::run_command(
+                let ctx = run::RunContext {
+                    pre_input: &mut pre_input,
+                    undo_buffer: undo_buffer.as_mut(),
+                    breakpoints: &mut breakpoints,
+                    symbol_table: &symbol_table,
+                    output: &mut output,
+                    interactive,
+                    trace: tracer.as_mut(),
+                    call_stack: &mut call_stack,
+                    faults: &faults,
+                    uninit: &mut uninit,
+                    profiler: &mut profiler,
+                    coverage: &mut coverage,
+                    loop_detector: &mut loop_detector,
+                };
+                let run = if cmd == Some("run") {
+                    run::run_command(&mut vm, &mut term, body, ctx)?
+                } else {
+                    let until = match cmd {
+                        Some("step") | Some("s") => run::RunUntil::Limit(1),
+                        Some("next") | Some("n") => run::RunUntil::StepOver,
+                        _ => run::RunUntil::StepOut,
+                    };
+                    run::execute(&mut vm, &mut term, until, ctx)?
+                };
+                let status = run.report(&symbol_table, &term)?;
+                at_watch_stop = matches!(run.stop, run::Stop::Watch(_));
+                let instructions = run.instructions;
+                executed += instructions;
+                if cmd == Some("run") || instructions > 1 {
+                    term.write_line(&format!(
+                        "{}개의 instruction을 실행했습니다.",
+                        instructions
+                    ))?;
+                }
+                if cmd != Some("run") {
+                    helper::print_register_status(&vm, &term)?;
+                }
+                if vm.mem[MCR] >> 15 > 0 {
+                    source_map.print_context(vm.pc as usize, &term)?;
+                }
+                Ok(status)
+            }
+            Some("reverse-step") | Some("rs") | Some("reverse-finish") | Some("rf")
+            | Some("reverse-continue") | Some("rc") => {
+                let until = match cmd {
+                    Some("reverse-step") | Some("rs") => reverse::ReverseUntil::Step,
+                    Some("reverse-finish") | Some("rf") => reverse::ReverseUntil::Finish,
+                    _ => reverse::ReverseUntil::Continue,
+                };
+                match reverse::reverse_command(
                     &mut vm,
-                    &mut term,
-                    body,
-                    &mut pre_input,
+                    &term,
+                    until,
+                    &mut at_watch_stop,
                     undo_buffer.as_mut(),
-                )?;
-                term.write_line(&format!(
-                    "{}개의 instruction을 실행했습니다.",
-                    instructions
-                ))?;
-                Ok(())
+                    &breakpoints,
+                    &symbol_table,
+                )? {
+                    Some(instructions) => {
+                        executed = executed.saturating_sub(instructions);
+                        if instructions > 0 {
+                            call_stack.mark_stale();
+                            helper::print_register_status(&vm, &term)?;
+                            source_map.print_context(vm.pc as usize, &term)?;
+                        }
+                        Ok(helper::Status::Done)
+                    }
+                    None => Ok(helper::Status::Failed),
+                }
+            }
+            Some("break") | Some("b") => Ok(breakpoints.break_command(
+                &vm,
+                &symbol_table,
+                &source_map,
+                &term,
+                body,
+            )?),
+            Some("watch") => Ok(breakpoints.watch_command(&vm, &symbol_table, &term, body)?),
+            Some("condition") => Ok(breakpoints.condition_command(&symbol_table, &term, body)?),
+            Some("ignore") => Ok(breakpoints.ignore_command(&term, body)?),
+            Some("delete") => Ok(breakpoints.delete_command(&term, body)?),
+            Some("enable") => Ok(breakpoints.enable_command(&term, body, true)?),
+            Some("disable") => Ok(breakpoints.enable_command(&term, body, false)?),
+            Some("breakpoints") => {
+                breakpoints.list_command(&symbol_table, &term)?;
+                Ok(helper::Status::Done)
             }
             Some("stat") => {
                 helper::print_register_status(&vm, &term)?;
-                Ok(())
+                if let Some(ref buf) = undo_buffer {
+                    buf.print_position(&term)?;
+                }
+                source_map.print_context(vm.pc as usize, &term)?;
+                Ok(helper::Status::Done)
             }
-            Some("input") => {
-                term.write_line(
-                    "미리 입력될 내용을 모두 쓴 후, Ctrl+W를 누르세요.",
+            Some("set") => Ok(set::set_command(
+                &mut vm,
+                undo_buffer.as_mut(),
+                &mut uninit,
+                &symbol_table,
+                &term,
+                body,
+            )?),
+            Some("input") => Ok(input::input_command(
+                &mut pre_input,
+                &mut term,
+                body,
+                interactive,
+            )?),
+            Some("profile") => Ok(profiler.profile_command(&vm, &symbol_table, &term, body)?),
+            Some("coverage") => Ok(coverage.coverage_command(
+                &vm,
+                &symbol_table,
+                &source_map,
+                &term,
+                body,
+            )?),
+            Some("trace") => Ok(trace::trace_command(&mut tracer, &term, body)?),
+            Some("checkpoint") | Some("cp") => Ok(checkpoints.checkpoint_command(
+                &vm,
+                executed,
+                &symbol_table,
+                &term,
+                body,
+            )?),
+            Some("restore") => {
+                let status = checkpoints.restore_command(
+                    &mut vm,
+                    &mut executed,
+                    undo_buffer.as_mut(),
+                    &term,
+                    body,
                 )?;
-                pre_input = helper::TermWrapper(&mut term).collect::<Result<Vec<u8>, _>>()?;
-                Ok(())
+                if status == helper::Status::Done {
+                    call_stack.reset();
+                    call_stack.mark_stale();
+                    helper::print_register_status(&vm, &term)?;
+                    source_map.print_context(vm.pc as usize, &term)?;
+                }
+                Ok(status)
             }
+            Some("snapshot") => {
+                match snapshot::snapshot_command(
+                    &mut vm,
+                    &mut symbol_table,
+                    &mut pre_input,
+                    undo_buffer.as_mut(),
+                    &term,
+                    body,
+                )? {
+                    Some(loaded) => {
+                        if loaded {
+                            call_stack.reset();
+                            call_stack.mark_stale();
+                            faults.forget_images();
+                            uninit.define_all();
+                        }
+                        Ok(helper::Status::Done)
+                    }
+                    None => Ok(helper::Status::Failed),
+                }
+            }
+            Some("uninit") => Ok(uninit.uninit_command(&term, body)?),
+            Some("loopcheck") => Ok(loop_detector.loopcheck_command(&term, body)?),
+            Some("fault") => Ok(faults.fault_command(&term, body)?),
+            Some("backtrace") | Some("bt") => {
+                call_stack.backtrace_command(&vm, &symbol_table, &term)?;
+                Ok(helper::Status::Done)
+            }
+            Some("output") => Ok(output.output_command(&term, body)?),
             Some("noinput") => {
                 pre_input = vec![];
                 term.write_line("미리 설정된 입력을 모두 지웠습니다.")?;
-                Ok(())
+                Ok(helper::Status::Done)
             }
             Some("turn") => {
                 vm.mem[MCR] = match body {
@@ -115,30 +584,64 @@ fn main() -> Result<(), Error> {
                         0b0000_0000_0000_0000u16
                     }
                     _ => {
-                        term.write_line("유효한 명령어가 아닙니다.")?;
+                        status = helper::report_error(&term, "유효한 명령어가 아닙니다.")?;
                         continue;
                     }
                 } + (vm.mem[MCR] & 0b0111_1111_1111_1111u16);
-                Ok(())
+                Ok(helper::Status::Done)
             }
             Some("undo") => match undo_buffer {
                 Some(ref mut buf) => match str::parse::<usize>(body.unwrap_or("1")) {
-                    Ok(n) => {
-                        buf.undo_command(&mut vm, &term, n)?;
-                        Ok(())
+                    Ok(n) => match buf.undo_command(&mut vm, &term, n)? {
+                        Some(n) => {
+                            executed = executed.saturating_sub(n);
+                            if n > 0 {
+                                call_stack.mark_stale();
+                            }
+                            Ok(helper::Status::Done)
+                        }
+                        None => Ok(helper::Status::Failed),
+                    },
+                    Err(err) => {
+                        let status = helper::report_error(&term, "유효한 명령어가 아닙니다.")?;
+                        term.write_line(&format!("{}", err))?;
+                        Ok(status)
                     }
+                },
+                None => {
+                    let status =
+                        helper::report_error(&term, "undo buffer가 설정되지 않았습니다.")?;
+                    term.write_line(
+                        "먼저 buffer <size> 명령어로 버퍼를 초기화하세요.",
+                    )?;
+                    Ok(status)
+                }
+            },
+            Some("redo") => match undo_buffer {
+                Some(ref mut buf) => match str::parse::<usize>(body.unwrap_or("1")) {
+                    Ok(n) => match buf.redo_command(&mut vm, &term, n)? {
+                        Some(n) => {
+                            executed += n;
+                            if n > 0 {
+                                call_stack.mark_stale();
+                            }
+                            Ok(helper::Status::Done)
+                        }
+                        None => Ok(helper::Status::Failed),
+                    },
                     Err(err) => {
-                        term.write_line("유효한 명령어가 아닙니다.")?;
+                        let status = helper::report_error(&term, "유효한 명령어가 아닙니다.")?;
                         term.write_line(&format!("{}", err))?;
-                        Ok(())
+                        Ok(status)
                     }
                 },
                 None => {
-                    term.write_line("undo buffer가 설정되지 않았습니다.")?;
+                    let status =
+                        helper::report_error(&term, "undo buffer가 설정되지 않았습니다.")?;
                     term.write_line(
                         "먼저 buffer <size> 명령어로 버퍼를 초기화하세요.",
                     )?;
-                    Ok(())
+                    Ok(status)
                 }
             },
             Some("buffer") | Some("buf") => match body
@@ -148,33 +651,36 @@ fn main() -> Result<(), Error> {
                 )
                 .and_then(|x| str::parse::<usize>(x).map_err(From::from))
             {
-                Ok(size) if size == 0 => {
+                Ok(0) => {
                     undo_buffer = None;
                     term.write_line("버퍼를 없앴습니다.")?;
-                    Ok(())
+                    Ok(helper::Status::Done)
                 }
                 Ok(size) => {
                     let expected_bytes =
-                        (std::mem::size_of::<Option<VM>>() as i64).wrapping_mul(size as i64);
+                        (UndoBuffer::entry_size() as i64).wrapping_mul(size as i64);
                     if expected_bytes >= 100 * (1 << 20) {
                         term.write_line(&format!(
-                            "경고: 100MiB 이상의 큰 메모리를 할당합니다. ({})",
+                            "경고: 기록이 가득 차면 100MiB 이상의 큰 메모리를 사용합니다. ({})",
                             convert(expected_bytes as f64)
                         ))?;
-                        loop {
-                            term.write_str("계속하시겠습니까? (y/n) ")?;
-                            let answer = term.read_line()?;
-                            match answer.as_ref() {
-                                "y" | "yes" | "d" | "dd" | "ㅇ" | "ㅇㅇ" | "예" | "네" => {
-                                    break;
-                                }
-                                "n" | "no" | "s" | "ss" | "ㄴ" | "ㄴㄴ" | "아니오"
-                                | "아뇨" => {
-                                    term.write_line("취소합니다.")?;
-                                    continue 'cmdloop;
-                                }
-                                _ => {
-                                    term.write_line("잘못된 입력입니다.")?;
+                        // 스크립트 모드에서는 묻지 않고 그대로 진행합니다.
+                        if interactive {
+                            loop {
+                                term.write_str("계속하시겠습니까? (y/n) ")?;
+                                let answer = term.read_line()?;
+                                match answer.as_ref() {
+                                    "y" | "yes" | "d" | "dd" | "ㅇ" | "ㅇㅇ" | "예" | "네" => {
+                                        break;
+                                    }
+                                    "n" | "no" | "s" | "ss" | "ㄴ" | "ㄴㄴ" | "아니오"
+                                    | "아뇨" => {
+                                        term.write_line("취소합니다.")?;
+                                        continue 'cmdloop;
+                                    }
+                                    _ => {
+                                        term.write_line("잘못된 입력입니다.")?;
+                                    }
                                 }
                             }
                         }
@@ -185,15 +691,15 @@ fn main() -> Result<(), Error> {
                         size
                     ))?;
                     term.write_line(&format!(
-                        "할당된 메모리: {}",
+                        "기록이 가득 찼을 때의 메모리: {}",
                         convert(expected_bytes as f64)
                     ))?;
-                    Ok(())
+                    Ok(helper::Status::Done)
                 }
                 Err(err) => {
-                    term.write_line("유효한 명령어가 아닙니다.")?;
+                    let status = helper::report_error(&term, "유효한 명령어가 아닙니다.")?;
                     term.write_line(&format!("{}", err))?;
-                    Ok(())
+                    Ok(status)
                 }
             },
             Some("sym") => match body {
@@ -201,15 +707,18 @@ fn main() -> Result<(), Error> {
                     let addr = match helper::parse_usize_with_prefix(addr) {
                         Ok(addr) => addr,
                         Err(err) => {
-                            term.write_line("잘못된 입력입니다.")?;
+                            status = helper::report_error(&term, "잘못된 입력입니다.")?;
                             term.write_line(&format!("{}", err))?;
                             continue;
                         }
                     };
                     term.write_line(&symbol::symbol_table_query(&symbol_table, addr))?;
-                    Ok(())
+                    Ok(helper::Status::Done)
+                }
+                None => {
+                    symbol::symbol_table_view(&symbol_table, &term)?;
+                    Ok(helper::Status::Done)
                 }
-                None => symbol::symbol_table_view(&symbol_table, &term),
             },
             Some("mem") => match body {
                 Some(body) => {
@@ -222,18 +731,18 @@ fn main() -> Result<(), Error> {
                     };
                     if range_str[0..1].parse::<u8>().is_ok() {
                         range_str = String::from("+") + &range_str;
-                    } else if &range_str[1..] == "" {
+                    } else if range_str[1..].is_empty() {
                         range_str = range_str.to_owned() + "10";
                     }
 
                     let addr: usize;
-                    if addr_str.to_ascii_lowercase() == "pc" {
+                    if addr_str.eq_ignore_ascii_case("pc") {
                         addr = vm.pc as usize;
                     } else {
                         match helper::parse_usize_with_prefix(addr_str) {
                             Ok(a) => addr = a,
                             Err(err) => {
-                                term.write_line("잘못된 입력입니다.")?;
+                                status = helper::report_error(&term, "잘못된 입력입니다.")?;
                                 term.write_line(&format!("{}", err))?;
                                 continue;
                             }
@@ -245,7 +754,7 @@ fn main() -> Result<(), Error> {
                     let n = match helper::parse_usize_with_prefix(&range_str[1..]) {
                         Ok(addr) => addr,
                         Err(err) => {
-                            term.write_line("잘못된 입력입니다.")?;
+                            status = helper::report_error(&term, "잘못된 입력입니다.")?;
                             term.write_line(&format!("{}", err))?;
                             continue;
                         }
@@ -265,7 +774,7 @@ fn main() -> Result<(), Error> {
                             upper = addr + n + 1;
                         }
                         _ => {
-                            term.write_line("잘못된 입력입니다.")?;
+                            status = helper::report_error(&term, "잘못된 입력입니다.")?;
                             continue;
                         }
                     }
@@ -273,23 +782,28 @@ fn main() -> Result<(), Error> {
                     upper = std::cmp::min(1 << 16, upper);
                     term.write_line("addr  binary           hex      uint    int   instruction           (symbol)")?;
                     for addr in lower..upper {
-                        helper::view_mem_entry(addr, &vm, &symbol_table, &term)?;
+                        helper::view_mem_entry(addr, &vm, &symbol_table, &source_map, &term)?;
                     }
-                    Ok(())
-                }
-                None => {
-                    term.write_line("잘못된 입력입니다.")?;
-                    Ok(())
+                    Ok(helper::Status::Done)
                 }
+                None => Ok(helper::report_error(&term, "잘못된 입력입니다.")?),
             },
             _ => {
-                term.write_line("유효한 명령어가 아닙니다.")?;
+                status = helper::report_error(&term, "유효한 명령어가 아닙니다.")?;
                 continue;
             }
         }?;
     }
 
+    if let Some(path) = coverage_file {
+        // 종료 코드는 VM의 상태로 정하므로, 저장하지 못했다면 알리기만 합니다.
+        let _ = coverage.save_and_report(&vm, &path, &term)?;
+    }
     term.write_line("ㅂ2")?;
+    if !interactive {
+        // VM이 정상적으로 멈췄는지를 종료 코드로 알립니다.
+        process::exit(if vm.mem[MCR] >> 15 == 0 { 0 } else { 1 });
+    }
     Ok(())
 }
 
@@ -298,20 +812,50 @@ fn help_command(_: &mut VM, term: &Term, body: Option<&str>) -> Result<(), Error
         term.write_str(
             r#"LC-3 디버거 명령어
     input: 프로그램에 입력될 내용을 미리 설정합니다.
+    input file <path>: 파일의 내용을 프로그램에 입력될 내용으로 설정합니다.
+    input "<내용>": escape(\n, \t, \x04 등)를 풀어 프로그램에 입력될 내용으로 설정합니다.
+    input append ...: 기존 입력을 지우지 않고 뒤에 덧붙입니다.
     noinput: input으로 설정된 내용을 모두 지웁니다.
+    output [clear|save <path>|mirror on|off]: 프로그램이 출력한 내용을 보거나, 지우거나, 저장합니다.
+    snapshot save|load <file>: VM의 상태, 심볼 테이블, 미리 설정된 입력을 파일로 저장하거나 불러옵니다.
+    cp, checkpoint <name>: 현재 VM의 상태를 <name>이라는 이름으로 저장합니다. 이름 없이 쓰면 목록을 출력합니다.
+    restore <name>: 저장한 checkpoint의 상태로 VM을 되돌립니다.
 
-    run: 프로그램이 종료될 때까지 실행합니다.
+    run: 프로그램이 종료될 때까지 실행합니다. 실행 중에 Ctrl+C를 누르면 멈춥니다.
+    s(tep): instruction 하나를 실행합니다.
+    n(ext): instruction 하나를 실행하되, JSR/JSRR/TRAP은 서브루틴이 돌아올 때까지 실행합니다.
+    finish: 현재 서브루틴이 RET할 때까지 실행합니다.
     stat: 현재 VM의 상태를 출력합니다.
+    bt, backtrace: 현재 위치까지의 서브루틴 호출 스택을 출력합니다.
+    fault [on|off]: 실행 오류(권한 위반, 데이터 실행 등) 검사를 켜거나 끕니다.
+    uninit [off|warn|stop]: 초기화되지 않은 레지스터나 메모리를 읽을 때 경고하거나 멈춥니다.
+    loopcheck [on|off|<n>]: 무한 루프에 빠지면 실행을 멈춥니다.
+    trace on <file> [text|csv|jsonl]: 실행하는 instruction을 한 줄씩 파일에 기록합니다.
+    trace off: 기록을 멈춥니다.
+    profile on|off|report [n]|clear: 주소별, 심볼별, 서브루틴별로 실행한 instruction의 수를 셉니다.
+    coverage on|off|report|list|save|merge: 실행된 instruction과 분기 방향을 기록하고 커버리지를 출력합니다.
+    set <target> <value>: 레지스터, PC, PSR, CC 또는 메모리의 값을 바꿉니다.
+
+    b(reak) <addr|symbol|file:line> [if <expr>]: 해당 위치에 breakpoint를 설정합니다.
+    watch <addr|symbol> [len] [r|w|rw] [if <expr>]: 해당 메모리 범위에 접근하면 멈추는 watchpoint를 설정합니다.
+    condition <id> [expr]: breakpoint/watchpoint의 조건식을 설정하거나 지웁니다.
+    ignore <id> <count>: breakpoint/watchpoint를 다음 <count>번 무시합니다.
+    delete <id>: breakpoint/watchpoint를 지웁니다. <id>가 없으면 모두 지웁니다.
+    enable/disable <id>: breakpoint/watchpoint를 활성화/비활성화합니다.
+    breakpoints: 설정된 breakpoint/watchpoint 목록을 출력합니다.
 
     turn on: VM의 Clock Enable Bit을 1로 만듭니다(VM을 켭니다).
     turn off: VM의 Clock Enable Bit을 0으로 만듭니다(VM을 끕니다).
 
     buf(fer) <size>: 최대 <size> instruction만큼 VM을 되돌릴 수 있는 버퍼를 생성합니다.
                      이미 버퍼가 있었다면, 기록을 삭제하고 새로 만듭니다. (undo가 불가능해집니다.)
-                     참고: 버퍼를 생성된 상태에서 프로그램을 실행하면 성능이 약간 감소할 수 있습니다.
-                     (매 instruction마다 VM을 복사해야 하기 때문입니다.)
+                     instruction마다 바뀌는 레지스터와 메모리 word만 기록하므로, 수백만 instruction도 기록할 수 있습니다.
     buf(fer) 0: 버퍼를 없앱니다.
     undo <n=1>: <n> instruction만큼 VM을 되돌립니다.
+    redo <n=1>: undo로 되돌린 instruction을 <n>개만큼 다시 적용합니다.
+    rs, reverse-step: instruction 하나를 거꾸로 실행합니다.
+    rf, reverse-finish: 현재 서브루틴을 호출하기 직전까지 거꾸로 실행합니다.
+    rc, reverse-continue: breakpoint/watchpoint에 마지막으로 걸렸던 곳까지 거꾸로 실행합니다.
 
     sym: 심볼 테이블을 봅니다.
     sym <addr>: 해당 위치로부터 가장 가까운 심볼을 찾습니다.
@@ -320,7 +864,12 @@ fn help_command(_: &mut VM, term: &Term, body: Option<&str>) -> Result<(), Error
     help: 이 도움말을 출력합니다.
     help <command>: 해당 명령어에 대한 도움말을 출력합니다.
 
-    bye: 디버거를 종료합니다.
+    bye: 디버거를 종료합니다. (빈 줄에서 Ctrl+D를 눌러도 종료합니다.)
+
+프롬프트에서는 ←/→, Home/End로 커서를 옮기고, ↑/↓로 이전에 입력한 명령어를 불러올 수 있습니다.
+명령어 기록은 ~/.lc3dbg_history에 저장되어 다음 실행에서도 쓸 수 있습니다.
+Tab을 누르면 명령어 이름, 심볼 이름, 레지스터 이름(r0-r7, pc, psr, cc)을 완성합니다.
+빈 줄을 입력하면 마지막 명령어가 step, next, finish 또는 stat일 때 다시 실행합니다.
 "#,
         )?;
         return Ok(());
@@ -335,21 +884,191 @@ fn help_command(_: &mut VM, term: &Term, body: Option<&str>) -> Result<(), Error
 이후 run 명령을 통해 프로그램을 실행하면, input에 의해 설정된 입력이 자동으로 프로그램에 전달됩니다.
 미리 입력된 내용을 다 전달했음에도 프로그램이 계속 입력을 요청하면, 그 때부터는 터미널의 입력을 프로그램에 전달합니다.
 (noinput 상태처럼)
-input 명령을 여러 번 실행하면, 가장 마지막에 설정된 입력만 작동하며 이전의 설정은 모두 지워집니다.",
+input 명령을 여러 번 실행하면, 가장 마지막에 설정된 입력만 작동하며 이전의 설정은 모두 지워집니다.
+input <내용>: Ctrl+W를 누르는 대신, 같은 줄에 주어진 내용을 그대로 입력으로 설정합니다.
+input file <path>: 파일의 내용을 그대로 입력으로 설정합니다. (lc3dbg --input <path>와 같습니다.)
+input \"<내용>\": \"로 둘러싼 내용의 escape를 풀어 입력으로 설정합니다.
+    \\n(줄바꿈), \\t(탭), \\r, \\0, \\\\, \\\", \\xHH(16진수 바이트)를 쓸 수 있습니다. (예: input \"abc\\n\\x04\")
+input append <...>: 위의 형태들 앞에 append를 붙이면, 기존 입력을 지우지 않고 뒤에 덧붙입니다.
+    (예: input append file more.txt, input append \"\\n\", 내용 없이 input append만 쓰면 Ctrl+W까지 읽어 덧붙입니다.)
+스크립트 모드(--script)에서는 Ctrl+W로 입력하는 형태를 쓸 수 없고, 미리 설정된 입력을 다 전달한 후에도
+프로그램이 입력을 요청하면 터미널에서 읽지 않고 실행을 멈춥니다.",
+            "output" => "output: 지금까지 프로그램이 콘솔에 출력한 내용을 모두 출력합니다.
+output clear: 모인 출력을 지웁니다.
+output save <path>: 모인 출력을 파일로 저장합니다.
+output mirror on/off: 프로그램의 출력을 모으는 동시에 터미널에도 출력할지 정합니다. 기본값은 on입니다.
+    off로 두면 프로그램의 출력은 터미널에 보이지 않고, output 명령어로만 확인할 수 있습니다.
+    프로그램의 출력이 줄바꿈으로 끝나지 않은 채 실행이 멈추면, 디버거의 메시지와 섞이지 않도록 줄을 바꿉니다.
+    undo로 VM을 되돌려도 모인 출력은 지워지지 않습니다.",
+            "snapshot" => "snapshot save <file>: VM의 모든 상태(레지스터, PC, IR, PSR, 메모리 전체)와 심볼 테이블,
+    미리 설정된 입력을 파일로 저장합니다. 스택과 장치 레지스터(KBSR, DSR, MCR 등)는 메모리에 있으므로 함께 저장됩니다.
+snapshot load <file>: 저장한 스냅샷을 불러와 VM, 심볼 테이블, 미리 설정된 입력을 바꿉니다.
+    undo buffer의 기록은 지워지며, breakpoint/watchpoint, 모인 출력, 소스 정보는 그대로 남습니다.
+lc3dbg --snapshot <file>로 스냅샷에서 바로 시작할 수도 있습니다.",
+            "uninit" => "uninit warn: 사용자 프로그램(x3000 이상)의 instruction이 초기화되지 않은 레지스터나 메모리를 읽으면,
+    instruction과 심볼, 읽은 레지스터/메모리를 경고로 출력하고 계속 실행합니다. 같은 instruction은 한 번만 경고합니다.
+uninit stop: 경고하는 대신 그 instruction을 실행하기 전에 멈춥니다.
+uninit off: 검사하지 않습니다. 기본값입니다.
+uninit: 현재 설정을 출력합니다.
+    프로그램을 시작할 때 R0~R7은 모두 초기화되지 않은 것으로 봅니다.
+    메모리는 불러온 프로그램이 차지하는 곳(.BLKW로 잡아 둔 곳은 제외), 운영체제 영역, 장치 레지스터만 초기화된 것으로 봅니다.
+    instruction이 값을 쓰거나, set 명령어로 값을 바꾸면 초기화된 것이 됩니다.
+    AND Rn, Rn, #0은 레지스터를 읽지 않는 것으로 봅니다.
+    snapshot load 후에는 모든 레지스터와 메모리를 초기화된 것으로 봅니다.",
+            "fault" => "fault on/off: 실행 오류 검사를 켜거나 끕니다. 기본값은 on입니다.
+fault: 검사가 켜져 있는지 출력합니다.
+    run/step/next/finish는 instruction을 실행하기 전에 다음을 검사하고, 해당하면 실행하지 않고 멈춥니다.
+    - 예약된 opcode(1101)
+    - RTI (사용자 모드라면 privilege mode violation입니다. 이 VM은 RTI를 지원하지 않습니다.)
+    - 사용자 모드의 프로그램(x3000 이상)이 운영체제 영역(x0000~x2FFF)이나 장치 레지스터(xFE00~xFFFF)에 접근
+    - .FILL/.BLKW/.STRINGZ로 만든 데이터를 instruction으로 실행 (.asm 파일이나 같은 이름의 .asm이 있는 경우)
+    - 프로그램을 불러오지 않은 메모리를 실행하거나, 불러온 프로그램의 끝을 지나 실행
+    fault off로 두면 예약된 opcode와 RTI만 검사합니다. (이 둘은 VM이 실행할 수 없습니다.)
+    lc3dbg --no-fault로 실행하면 처음부터 꺼진 상태로 시작합니다. (test에도 적용됩니다.)
+    장치 레지스터를 직접 읽는 프로그램처럼 일부러 규칙을 벗어나는 경우에 끄세요.
+    snapshot load 후에는 어디에 프로그램을 불러왔는지 알 수 없으므로, 메모리 전체를 불러온 것으로 봅니다.",
+            "backtrace" | "bt" => "bt, backtrace: run/step/next/finish로 실행한 JSR/JSRR/TRAP과 RET을 따라 만든 호출 스택을 출력합니다.
+    #0은 현재 PC이고, #1부터는 안쪽 호출부터 차례로 호출된 서브루틴(x주소와 심볼)과 호출한 instruction의 주소를 보여줍니다.
+    RET이 가장 안쪽 호출의 다음 주소가 아닌 곳으로 돌아갔다면(R7이 덮어씌워진 경우 등), 경고와 함께 표시합니다.
+    undo, reverse-*, restore, snapshot load로 VM을 바꾼 후에는 호출 스택이 실제와 다를 수 있습니다.",
+            "profile" => "profile on: run/step/next/finish로 실행하는 instruction의 수를 주소마다 세기 시작합니다.
+profile off: 세기를 멈춥니다. 지금까지의 기록은 남아 있습니다.
+profile report [n=10]: 기록을 다음 세 가지로 나누어 많은 순서대로 n개씩 출력합니다.
+    - 가장 많이 실행된 instruction (주소별)
+    - 가장 많이 실행된 심볼 (레이블부터 다음 레이블 전까지의 instruction을 모아서)
+    - 서브루틴별 호출 횟수와 inclusive/exclusive 실행 횟수
+      서브루틴은 JSR/JSRR/TRAP으로 호출된 주소입니다. inclusive는 서브루틴이 호출한 다른 서브루틴의 instruction까지,
+      exclusive는 서브루틴 자신의 instruction만 셉니다. (최상위)는 어떤 서브루틴에도 속하지 않은 코드입니다.
+profile clear: 기록을 지웁니다.
+profile: 프로파일러가 켜져 있는지와 기록한 instruction의 수를 출력합니다.",
+            "coverage" => "coverage on: 불러온 프로그램의 instruction마다 실행 횟수를, 조건 분기(BR)마다 분기한/하지 않은 횟수를 기록합니다.
+coverage off: 기록을 멈춥니다. 지금까지의 기록은 남아 있습니다.
+coverage clear: 기록을 지웁니다.
+coverage report: instruction과 분기 방향의 커버리지를 출력하고, 실행되지 않은 instruction과 한쪽으로만 실행된 분기를 나열합니다.
+coverage list: 프로그램의 모든 instruction을 실행 횟수(-----는 실행되지 않음), 분기 횟수(T: 분기함, F: 분기하지 않음)와 함께 출력합니다.
+    실행되지 않았거나 한쪽으로만 분기한 줄은 맨 앞에 !가 붙습니다.
+coverage save <file>: 기록을 다른 실행과 합칠 수 있는 형식으로 저장합니다. (주소마다 `주소 실행 분기 비분기` 한 줄)
+coverage merge <file>: 저장된 기록을 현재 기록에 더합니다.
+커버리지는 .asm으로 불러온 프로그램의 instruction(데이터 제외), 또는 오브젝트 파일이 차지하는 x3000 이후의 word로 계산합니다.
+lc3dbg --coverage <file>로 실행하면 파일의 기록에 이어서 처음부터 기록하고, 종료할 때 파일에 저장합니다.",
+            "loopcheck" => "loopcheck: 무한 루프 검사가 켜져 있는지 출력합니다. 기본으로 켜져 있습니다.
+loopcheck on|off: 무한 루프 검사를 켜거나 끕니다.
+loopcheck <n>: 검사를 켜고, 좁은 루프로 판단할 instruction의 수를 <n>으로 정합니다. (기본값 1000000)
+다음의 경우 run/next/finish를 멈추고 루프의 주소 범위와 심볼을 출력합니다.
+    - 메모리 쓰기와 입출력(장치 레지스터 접근) 없이 같은 상태(PC, 레지스터, CC)로 돌아온 경우. 반드시 무한 루프입니다.
+    - 입출력 없이 64 word 이내의 범위에서만 <n>개의 instruction을 실행한 경우. 무한 루프일 가능성이 높습니다.",
+            "trace" => "trace on <file> [text|csv|jsonl]: run/step/next/finish로 실행하는 instruction을 한 줄씩 파일에 기록합니다.
+    각 줄에는 순번, PC, 심볼, instruction, 바뀐 레지스터, 메모리에 쓴 값, 실행 후의 CC가 들어갑니다.
+    형식을 주지 않으면 파일의 확장자로 정합니다. (.csv는 csv, .jsonl/.json은 jsonl, 그 외는 text)
+    - text: 사람이 읽기 위한 형식입니다.
+    - csv: seq,pc,symbol,instruction,registers,memory,cc 열을 가진 CSV입니다.
+      registers는 R1:x0000>x0001처럼 공백으로 구분하고, memory는 x3004:x0000>x0001처럼 씁니다.
+    - jsonl: 한 줄에 JSON object 하나씩 씁니다. 주소와 값은 10진수 정수입니다.
+      (예: {\"seq\":1,\"pc\":12288,\"symbol\":\"L\",\"instruction\":\"ADD r1, r1, #1\",
+            \"registers\":[{\"reg\":1,\"old\":0,\"new\":1}],\"memory\":[],\"cc\":\"p\"})
+    이미 기록하고 있었다면 이전 파일을 닫고 새 파일에 기록합니다.
+trace off: 기록을 멈추고 파일을 닫습니다.
+trace: 지금 기록하고 있는 파일과 기록한 instruction의 수를 출력합니다.",
+            "checkpoint" | "cp" => "cp, checkpoint <name>: 현재 VM의 상태를 <name>이라는 이름으로 메모리에 저장합니다.
+    같은 이름의 checkpoint가 있다면 덮어씁니다.
+cp, checkpoint: 저장된 checkpoint의 이름, PC, 심볼, 그때까지 실행한 instruction의 수를 출력합니다.
+cp, checkpoint delete <name>: checkpoint를 지웁니다.
+    undo buffer와 달리 개수에 제한이 없고, restore <name>으로 같은 checkpoint에 몇 번이든 돌아갈 수 있습니다.",
+            "restore" => "restore <name>: VM의 상태(레지스터, PC, PSR, 메모리 전체)를 checkpoint를 저장했을 때로 되돌립니다.
+    미리 설정된 입력(input), 모인 출력, breakpoint는 그대로이므로, 같은 지점에서 다른 입력으로 여러 번 실행해 볼 수 있습니다.
+    undo buffer의 기록은 지워집니다.",
             "noinput" => "input 명령으로 설정된 입력을 모두 지웁니다. 이후 run 명령을 통해 프로그램을 실행하면, 터미널의 입력을 프로그램에 전달합니다.",
             "run" => "run: 프로그램을 VM이 종료될 때까지 실행합니다.
 run n: 프로그램이 종료될 때까지 최대 n개의 instruction을 실행합니다.
-       서브루틴, TRAP 내부의 instruction 또한 모두 포함합니다.",
-            "stat" => "stat: 현재 VM의 상태를 출력합니다.",
+       서브루틴, TRAP 내부의 instruction 또한 모두 포함합니다.
+활성화된 breakpoint가 걸린 instruction에 도달하면, 그 instruction을 실행하기 전에 멈춥니다.
+(단, run을 시작한 위치의 breakpoint는 무시합니다.)
+미리 설정된 입력(input)은 프로그램이 읽어간 만큼 소모됩니다.
+실행 중에 Ctrl+C를 누르면 지금 실행 중인 instruction까지만 실행하고 멈춥니다.
+프로그램이 GETC, IN 등으로 키 입력을 기다리는 중이라면, 입력을 읽는 instruction을 실행하기 전에 멈춥니다.
+VM의 상태, undo buffer와 남은 입력은 그대로 남아 있으므로, 다시 run으로 이어서 실행할 수 있습니다.",
+            "break" | "b" => "b(reak) <addr|symbol|file:line> [if <expr>]: 해당 위치에 breakpoint를 설정합니다.
+    addr 변수는 x1234와 같이 16진수로, 또는 1234와 같이 10진수로 표현하거나, PC(pc) 또는 심볼 이름을 입력할 수 있습니다.
+    소스 정보가 있다면 prog.asm:42 처럼 소스 파일의 줄을 지정할 수 있습니다.
+    해당 줄에 코드가 없다면, 그 뒤로 가장 먼저 나오는 코드에 설정합니다.
+    if <expr>이 주어지면 조건식이 참일 때만 멈춥니다. 조건식의 문법은 help condition을 참고하세요.
+    설정된 breakpoint에는 번호가 붙으며, delete/enable/disable/condition/ignore 명령어에서 이 번호를 사용합니다.",
+            "condition" => "condition <id> <expr>: 해당 번호의 breakpoint/watchpoint에 조건식을 설정합니다.
+condition <id>: 조건식을 지웁니다.
+    조건식에는 다음을 쓸 수 있습니다.
+    - 숫자: x41(16진수), 65 또는 #65(10진수)
+    - 레지스터: R0~R7, PC, IR, PSR
+    - 메모리: mem[<expr>] (예: mem[COUNT], mem[R6 + 1])
+    - 심볼 이름: 심볼의 주소로 바뀝니다.
+    - CC: cc == n, cc != zp 처럼 플래그와 비교합니다.
+    - 연산자: + - * (산술), == != < <= > >= (비교), && || ! (논리), 괄호
+    모든 값은 부호 있는 16비트 값으로 해석합니다. (xFFFF는 -1과 같습니다.)
+    예: R0 == x41 && mem[COUNT] > 3 && cc == z
+    watchpoint의 조건식은 instruction을 실행한 후의 상태로 계산합니다.",
+            "ignore" => "ignore <id> <count>: 해당 번호의 breakpoint/watchpoint를 조건을 만족하더라도 다음 <count>번 무시합니다.
+    breakpoints 명령어로 각 breakpoint에 도달한 횟수(hits)를 확인할 수 있습니다.",
+            "watch" => "watch <addr|symbol> [len] [r|w|rw] [if <expr>]: <addr>부터 <len>개의 메모리 word에 대한 watchpoint를 설정합니다.
+    len: 감시할 word의 개수입니다. 주어지지 않으면 1입니다.
+    r: 읽기, w: 쓰기, rw: 읽기와 쓰기 모두를 감시합니다. 주어지지 않으면 w입니다.
+    LD/LDI/LDR/ST/STI/STR instruction이 범위에 접근하면(LDI/STI의 포인터를 읽는 것 포함),
+    그 instruction을 실행한 직후에 멈추고 이전 값, 새 값, 해당 instruction의 PC를 출력합니다.
+    watchpoint는 breakpoint와 같은 번호 체계를 사용하므로, delete/enable/disable 명령어로 관리합니다.",
+            "delete" => "delete <id>: 해당 번호의 breakpoint를 지웁니다.
+delete: 모든 breakpoint를 지웁니다.",
+            "enable" | "disable" => "enable <id>: 해당 번호의 breakpoint를 활성화합니다.
+disable <id>: 해당 번호의 breakpoint를 비활성화합니다. 비활성화된 breakpoint에서는 멈추지 않습니다.
+<id>가 주어지지 않으면 모든 breakpoint에 적용합니다.",
+            "breakpoints" => "breakpoints: 설정된 breakpoint의 번호, 주소, 활성화 여부, 심볼을 출력합니다.",
+            "step" | "s" => "s(tep): instruction 하나를 실행한 후 VM의 상태를 출력합니다.
+        JSR/JSRR/TRAP을 만나면 서브루틴 안으로 들어갑니다.",
+            "next" | "n" => "n(ext): instruction 하나를 실행한 후 VM의 상태를 출력합니다.
+        JSR/JSRR/TRAP을 만나면 그 다음 주소(돌아올 주소)에 도착할 때까지 한 번에 실행합니다.
+        서브루틴 안에서 breakpoint/watchpoint에 걸리면 그 자리에서 멈춥니다.",
+            "finish" => "finish: 현재 서브루틴을 호출한 JSR/JSRR/TRAP의 다음 주소로 돌아갈 때까지 실행한 후 VM의 상태를 출력합니다.
+        R7을 스택에 저장했다 되돌리거나 R7이 아닌 레지스터로 JMP해 돌아가도 멈춥니다.
+        R7이 바뀌어 다른 곳으로 돌아가더라도, 서브루틴에서 빠져나오면 그곳에서 멈춥니다.
+        서브루틴 안이 아니라면(bt에 호출이 없다면), 또는 undo 등으로 호출 스택이 정확하지 않다면 실행하지 않습니다.
+step/next/finish 모두 undo buffer가 설정되어 있다면 undo로 되돌릴 수 있습니다.",
+            "stat" => "stat: 현재 VM의 상태를 출력합니다.
+      undo buffer가 있다면 기록 안에서의 현재 위치(기록: 현재/전체, undo/redo 가능한 instruction 수)를 함께 출력합니다.
+      소스 정보가 있다면 현재 PC에 해당하는 소스 위치와 앞뒤 줄을 함께 출력합니다.
+      소스 정보는 .asm 파일을 직접 열거나, .obj 파일과 같은 이름의 .asm 파일이 있을 때 만들어집니다.",
+            "set" => "set <target> <value>: VM의 상태를 바꿉니다. (set <target> = <value> 형태도 가능합니다.)
+    target으로 다음을 쓸 수 있습니다.
+    - r0~r7: 범용 레지스터 (예: set r3 x1234)
+    - pc: Program Counter (예: set pc LOOP)
+    - psr: Processor Status Register. supervisor, priority, CC가 함께 바뀝니다.
+    - cc: Condition Code. value로 n, z, p를 씁니다. (예: set cc z)
+    - supervisor: Supervisor 모드. value로 yes/no를 씁니다.
+    - mem[<addr>]: 메모리 (예: set mem[x3000] #-5, set mem[COUNT] 0)
+    value는 x1234(16진수), 1234 또는 #-5(10진수), 심볼 이름(심볼의 주소)이나 조건식과 같은 식으로 쓸 수 있습니다.
+    undo buffer가 설정되어 있다면, set 바로 뒤의 undo로 바꾼 값만 되돌릴 수 있습니다.
+    그 뒤에 instruction을 실행했다면, set보다 앞의 instruction을 되돌릴 때 함께 되돌아갑니다.
+    set은 instruction으로 세지 않으므로 undo, reverse-* 명령어와 stat의 기록 위치에 영향을 주지 않습니다.",
             "turn" => "turn on: VM의 Clock Enable Bit을 1로 만듭니다(VM을 켭니다).
 turn off: VM의 Clock Enable Bit을 0으로 만듭니다(VM을 끕니다).",
             "buffer" | "buf" => "buf(fer) <size>: 최대 <size> instruction만큼 VM을 되돌릴 수 있는 버퍼를 생성합니다.
                  이미 버퍼가 있었다면, 기록을 삭제하고 새로 만듭니다. (undo가 불가능해집니다.)
-                 참고: 버퍼를 생성된 상태에서 프로그램을 실행하면 성능이 약간 감소할 수 있습니다.
-                 (매 instruction마다 VM을 복사해야 하기 때문입니다.)
+                 VM 전체를 복사하지 않고 instruction마다 바뀌는 레지스터, PC, PSR과 메모리 word만 기록합니다.
+                 (기록 하나에 수십 바이트이므로, 버퍼의 크기를 수백만으로 두어도 됩니다.)
 buf(fer) 0: 버퍼를 없앱니다.",
             "undo" => "undo <n>: <n> instruction만큼 VM을 되돌립니다.
-          undo를 취소할 수는 없으니 주의하세요.",
+          가장 최근의 기록이 set으로 바꾼 값이라면, 그 값 하나만 되돌립니다.
+          되돌린 기록은 남아 있어 redo로 다시 적용할 수 있습니다.
+          단, 되돌린 상태에서 프로그램을 실행하거나 set으로 값을 바꾸면 되돌린 기록은 지워집니다.",
+            "redo" => "redo <n>: undo(또는 rs/rf/rc)로 되돌린 instruction을 <n>개만큼 다시 적용합니다.
+          새로 실행하거나 set으로 값을 바꾸어 기록이 갈라지기 전까지만 사용할 수 있습니다.
+          stat 명령어로 기록 안에서의 현재 위치(기록: 현재/전체)를 확인할 수 있습니다.
+          프로그램이 읽어간 입력과 출력은 되돌아오거나 다시 적용되지 않습니다.",
+            "reverse-step" | "rs" | "reverse-finish" | "rf" | "reverse-continue" | "rc" => "undo buffer의 기록을 이용해 프로그램을 거꾸로 실행합니다. (먼저 buf(fer) <size>로 버퍼를 만들어야 합니다.)
+rs, reverse-step: instruction 하나를 되돌립니다.
+rf, reverse-finish: 현재 서브루틴을 호출한 JSR/JSRR/TRAP을 실행하기 직전까지 되돌립니다.
+rc, reverse-continue: 활성화된 breakpoint/watchpoint의 조건이 마지막으로 참이었던 곳까지 되돌립니다.
+    breakpoint는 해당 instruction을 실행하기 직전, watchpoint는 메모리에 접근한 instruction을 실행한 직후에서 멈춥니다.
+    ignore 횟수는 무시하며, hit 횟수도 바뀌지 않습니다.
+    걸리는 곳이 없다면 기록의 처음까지 되돌립니다.
+되돌린 후에는 되돌리기 전과 비교해 바뀐 레지스터, CC, 메모리 값을 출력합니다.
+되돌린 instruction의 기록은 undo와 마찬가지로 지워지며, 프로그램이 읽어간 입력과 출력은 되돌아오지 않습니다.",
             "sym" => "sym: 심볼 테이블을 출력합니다.
 sym <addr>: 해당 위치로부터 가장 가까운 심볼을 찾습니다.
             해당 위치에서 앞으로만 검색합니다.
@@ -360,7 +1079,8 @@ sym <addr>: 해당 위치로부터 가장 가까운 심볼을 찾습니다.
     -n: 주어진 메모리 위치 및 이전 n개의 메모리 값을 확인합니다. (최대 n개)
     ~n: 주어진 메모리 위치 및 앞뒤 n개의 메모리 값을 확인합니다. (최대 2n+1개)
     n이 주어지지 않는다면 10, +/-/~가 주어지지 않는다면 +가 기본으로 주어집니다.
-    n 변수는 x1234와 같이 16진수로, 또는 1234와 같이 10진수로 표현할 수 있습니다.",
+    n 변수는 x1234와 같이 16진수로, 또는 1234와 같이 10진수로 표현할 수 있습니다.
+    소스 정보가 있다면 각 메모리 값을 만든 소스 위치와 내용을 함께 출력합니다.",
             "help" => "help: 이 도움말을 출력합니다.
 help <command>: 해당 명령어에 대한 도움말을 출력합니다.",
             _ => "존재하지 않는 명령어입니다.",
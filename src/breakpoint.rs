@@ -0,0 +1,523 @@
+use crate::access::MemoryAccess;
+use crate::expr::Expr;
+use crate::helper::{parse_address, parse_usize_with_prefix, report_error, Status};
+use crate::source::SourceMap;
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::VM;
+use std::collections::BTreeMap;
+use std::io::Result as IOResult;
+
+/// watchpoint가 반응하는 메모리 접근 종류
+#[derive(Clone, Copy, PartialEq)]
+pub enum WatchAccess {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchAccess {
+    fn parse(s: &str) -> Option<WatchAccess> {
+        match s {
+            "r" => Some(WatchAccess::Read),
+            "w" => Some(WatchAccess::Write),
+            "rw" | "wr" => Some(WatchAccess::ReadWrite),
+            _ => None,
+        }
+    }
+
+    fn reads(self) -> bool {
+        self != WatchAccess::Write
+    }
+
+    fn writes(self) -> bool {
+        self != WatchAccess::Read
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            WatchAccess::Read => "r",
+            WatchAccess::Write => "w",
+            WatchAccess::ReadWrite => "rw",
+        }
+    }
+}
+
+pub enum BreakpointKind {
+    /// 해당 주소의 instruction을 실행하기 전에 멈춥니다.
+    Code,
+    /// [addr, addr + len) 범위의 메모리에 접근한 instruction을 실행한 후 멈춥니다.
+    Watch { len: usize, access: WatchAccess },
+}
+
+/// breakpoint에 붙는 조건식. source는 목록에 보여주기 위한 원래 문자열입니다.
+pub struct Condition {
+    pub source: String,
+    pub expr: Expr,
+}
+
+/// breakpoint 또는 watchpoint 하나
+pub struct Breakpoint {
+    pub addr: usize,
+    pub kind: BreakpointKind,
+    pub enabled: bool,
+    pub condition: Option<Condition>,
+    /// 조건을 만족하며 도달한 횟수(무시된 경우 포함)
+    pub hits: usize,
+    /// 앞으로 무시할 횟수
+    pub ignore: usize,
+}
+
+impl Breakpoint {
+    /// 위치에 도달했을 때 호출합니다. 조건을 검사하고 hit/ignore 횟수를 갱신한 후,
+    /// 실제로 멈춰야 하는지 반환합니다.
+    fn trigger(&mut self, vm: &VM) -> bool {
+        if !self.condition_holds(vm) {
+            return false;
+        }
+        self.hits += 1;
+        if self.ignore > 0 {
+            self.ignore -= 1;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// 조건식이 없거나 조건식이 참이라면 true를 반환합니다.
+    fn condition_holds(&self, vm: &VM) -> bool {
+        self.condition
+            .as_ref()
+            .is_none_or(|cond| cond.expr.is_true(vm))
+    }
+
+    fn watches(&self, addr: usize, write: bool) -> bool {
+        match self.kind {
+            BreakpointKind::Watch { len, access } => {
+                self.enabled
+                    && self.addr <= addr
+                    && addr < self.addr + len
+                    && if write {
+                        access.writes()
+                    } else {
+                        access.reads()
+                    }
+            }
+            BreakpointKind::Code => false,
+        }
+    }
+}
+
+/// "<target> if <expr>" 형태의 입력을 target과 조건식으로 나눕니다.
+fn split_condition(body: &str) -> (&str, Option<&str>) {
+    match body.find(" if ") {
+        Some(idx) => (body[..idx].trim(), Some(body[idx + 4..].trim())),
+        None => (body.trim(), None),
+    }
+}
+
+fn parse_condition(
+    source: Option<&str>,
+    symbol_table: &[TableEntry],
+) -> Result<Option<Condition>, String> {
+    match source {
+        Some(source) if !source.is_empty() => Ok(Some(Condition {
+            source: source.to_owned(),
+            expr: Expr::parse(source, symbol_table)?,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// watchpoint에 걸린 메모리 접근. old는 instruction 실행 전의 값입니다.
+pub struct WatchHit {
+    pub id: usize,
+    pub addr: usize,
+    pub write: bool,
+    pub old: u16,
+}
+
+/// 번호(id)로 관리되는 breakpoint/watchpoint 목록. 둘은 같은 번호 체계를 공유합니다.
+/// run_command는 매 instruction을 실행하기 전에 hit으로 현재 PC를,
+/// watch_hits로 해당 instruction의 메모리 접근을 검사합니다.
+#[derive(Default)]
+pub struct BreakpointSet {
+    entries: BTreeMap<usize, Breakpoint>,
+    last_id: usize,
+}
+
+impl BreakpointSet {
+    pub fn new() -> BreakpointSet {
+        Default::default()
+    }
+
+    /// breakpoint를 추가하고 그 번호를 반환합니다.
+    pub fn insert(
+        &mut self,
+        addr: usize,
+        kind: BreakpointKind,
+        condition: Option<Condition>,
+    ) -> usize {
+        self.last_id += 1;
+        let id = self.last_id;
+        self.entries.insert(
+            id,
+            Breakpoint {
+                addr,
+                kind,
+                enabled: true,
+                condition,
+                hits: 0,
+                ignore: 0,
+            },
+        );
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Breakpoint> {
+        self.entries.remove(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Breakpoint> {
+        self.entries.get(&id)
+    }
+
+    /// 해당 번호의 breakpoint가 없으면 false를 반환합니다.
+    pub fn set_enabled(&mut self, id: usize, enabled: bool) -> bool {
+        match self.entries.get_mut(&id) {
+            Some(bp) => {
+                bp.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 현재 PC에 걸린 활성화된 breakpoint 중 멈춰야 하는 것의 번호를 반환합니다.
+    /// PC에 걸린 모든 breakpoint의 hit 횟수가 갱신됩니다.
+    pub fn hit(&mut self, vm: &VM) -> Option<usize> {
+        let mut stop = None;
+        for (&id, bp) in self.entries.iter_mut() {
+            if bp.enabled
+                && bp.addr == vm.pc as usize
+                && matches!(bp.kind, BreakpointKind::Code)
+                && bp.trigger(vm)
+                && stop.is_none()
+            {
+                stop = Some(id);
+            }
+        }
+        stop
+    }
+
+    /// 실행될 instruction의 메모리 접근 중 watchpoint에 걸리는 것들을 반환합니다.
+    pub fn watch_hits(&self, vm: &VM, access: &MemoryAccess) -> Vec<WatchHit> {
+        let accesses = access
+            .reads
+            .iter()
+            .map(|&addr| (addr, false))
+            .chain(access.write.map(|addr| (addr, true)));
+        let mut hits = vec![];
+        for (addr, write) in accesses {
+            for (&id, bp) in self.entries.iter() {
+                if bp.watches(addr, write) {
+                    hits.push(WatchHit {
+                        id,
+                        addr,
+                        write,
+                        old: vm.mem[addr],
+                    });
+                }
+            }
+        }
+        hits
+    }
+
+    /// instruction을 실행한 후, watch_hits로 얻은 접근 중 조건과 ignore 횟수를 고려해
+    /// 실제로 멈춰야 하는 것들만 남깁니다. watchpoint 하나당 한 번만 검사합니다.
+    pub fn confirm_watch_hits(&mut self, vm: &VM, hits: Vec<WatchHit>) -> Vec<WatchHit> {
+        let mut triggered = BTreeMap::new();
+        hits.into_iter()
+            .filter(|hit| {
+                *triggered.entry(hit.id).or_insert_with(|| {
+                    self.entries
+                        .get_mut(&hit.id)
+                        .is_some_and(|bp| bp.trigger(vm))
+                })
+            })
+            .collect()
+    }
+
+    /// 기록된 상태 vm의 PC에 걸리는 활성화된 breakpoint의 번호를 반환합니다.
+    /// 거꾸로 실행할 때 사용하며, hit/ignore 횟수는 바꾸지 않고 무시합니다.
+    pub fn reverse_hit(&self, vm: &VM) -> Option<usize> {
+        self.entries
+            .iter()
+            .find(|(_, bp)| {
+                bp.enabled
+                    && bp.addr == vm.pc as usize
+                    && matches!(bp.kind, BreakpointKind::Code)
+                    && bp.condition_holds(vm)
+            })
+            .map(|(&id, _)| id)
+    }
+
+    /// watch_hits로 얻은 접근 중, instruction을 실행한 후의 상태 vm에서 조건을 만족하는 첫 번째를 반환합니다.
+    /// reverse_hit과 마찬가지로 hit/ignore 횟수는 바꾸지 않습니다.
+    pub fn reverse_watch_hit(&self, hits: Vec<WatchHit>, vm: &VM) -> Option<WatchHit> {
+        hits.into_iter()
+            .find(|hit| self.entries[&hit.id].condition_holds(vm))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Breakpoint)> {
+        self.entries.iter().map(|(&id, bp)| (id, bp))
+    }
+
+    pub fn break_command(
+        &mut self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        source_map: &SourceMap,
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let (target, condition) = split_condition(body.unwrap_or(""));
+        if target.is_empty() {
+            return report_error(term, "breakpoint를 설정할 주소나 심볼을 입력하세요.");
+        }
+        let condition = match parse_condition(condition, symbol_table) {
+            Ok(condition) => condition,
+            Err(err) => return report_error(term, &format!("잘못된 조건식입니다: {}", err)),
+        };
+        let addr = if target.contains(':') {
+            source_map.address_of(target)
+        } else {
+            parse_address(target, vm, symbol_table)
+        };
+        match addr {
+            Some(addr) => {
+                let id = self.insert(addr, BreakpointKind::Code, condition);
+                term.write_line(&format!(
+                    "breakpoint #{}: x{:04X} ({}){}",
+                    id,
+                    addr,
+                    symbol_table_query(symbol_table, addr),
+                    source_map
+                        .location(addr)
+                        .map_or(String::new(), |location| format!(" {}", location))
+                ))?;
+                Ok(Status::Done)
+            }
+            None => report_error(
+                term,
+                &format!(
+                    "잘못된 주소, 존재하지 않는 심볼 또는 코드가 없는 소스 위치입니다: {}",
+                    target
+                ),
+            ),
+        }
+    }
+
+    pub fn watch_command(
+        &mut self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let (args, condition) = split_condition(body.unwrap_or(""));
+        let condition = match parse_condition(condition, symbol_table) {
+            Ok(condition) => condition,
+            Err(err) => return report_error(term, &format!("잘못된 조건식입니다: {}", err)),
+        };
+        let mut args = args.split_whitespace();
+        let target = match args.next() {
+            Some(target) => target,
+            None => {
+                return report_error(term, "watchpoint를 설정할 주소나 심볼을 입력하세요.");
+            }
+        };
+        let addr = match parse_address(target, vm, symbol_table) {
+            Some(addr) => addr,
+            None => {
+                return report_error(
+                    term,
+                    &format!("잘못된 주소 또는 존재하지 않는 심볼입니다: {}", target),
+                );
+            }
+        };
+
+        let mut len = 1;
+        let mut access = WatchAccess::Write;
+        for arg in args {
+            if let Some(a) = WatchAccess::parse(arg) {
+                access = a;
+            } else {
+                match parse_usize_with_prefix(arg) {
+                    Ok(n) if n > 0 && addr + n <= 1 << 16 => len = n,
+                    _ => return report_error(term, &format!("잘못된 입력입니다: {}", arg)),
+                }
+            }
+        }
+
+        let id = self.insert(addr, BreakpointKind::Watch { len, access }, condition);
+        term.write_line(&format!(
+            "watchpoint #{}: x{:04X}~x{:04X} ({}) [{}]",
+            id,
+            addr,
+            addr + len - 1,
+            symbol_table_query(symbol_table, addr),
+            access.as_str()
+        ))?;
+        Ok(Status::Done)
+    }
+
+    pub fn condition_command(
+        &mut self,
+        symbol_table: &[TableEntry],
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let body = body.unwrap_or("").trim();
+        let (id, expr) = match body.find(' ') {
+            Some(idx) => (&body[..idx], Some(body[idx + 1..].trim())),
+            None => (body, None),
+        };
+        let id = match id.parse::<usize>() {
+            Ok(id) => id,
+            Err(err) => {
+                let status = report_error(term, "잘못된 입력입니다.")?;
+                term.write_line(&format!("{}", err))?;
+                return Ok(status);
+            }
+        };
+        let condition = match parse_condition(expr, symbol_table) {
+            Ok(condition) => condition,
+            Err(err) => return report_error(term, &format!("잘못된 조건식입니다: {}", err)),
+        };
+        match self.entries.get_mut(&id) {
+            Some(bp) => {
+                bp.condition = condition;
+                match &bp.condition {
+                    Some(cond) => term.write_line(&format!(
+                        "{}번 breakpoint의 조건을 설정했습니다: {}",
+                        id, cond.source
+                    ))?,
+                    None => {
+                        term.write_line(&format!("{}번 breakpoint의 조건을 지웠습니다.", id))?
+                    }
+                }
+                Ok(Status::Done)
+            }
+            None => report_error(term, &format!("{}번 breakpoint가 없습니다.", id)),
+        }
+    }
+
+    pub fn ignore_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        let mut args = body.unwrap_or("").split_whitespace();
+        let parsed = (
+            args.next().map(str::parse::<usize>),
+            args.next().map(str::parse::<usize>),
+        );
+        match parsed {
+            (Some(Ok(id)), Some(Ok(count))) => match self.entries.get_mut(&id) {
+                Some(bp) => {
+                    bp.ignore = count;
+                    term.write_line(&format!(
+                        "{}번 breakpoint를 다음 {}번 무시합니다.",
+                        id, count
+                    ))?;
+                    Ok(Status::Done)
+                }
+                None => report_error(term, &format!("{}번 breakpoint가 없습니다.", id)),
+            },
+            _ => report_error(term, "사용법: ignore <id> <count>"),
+        }
+    }
+
+    pub fn delete_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        match body.map(str::trim) {
+            None | Some("") => {
+                self.clear();
+                term.write_line("모든 breakpoint를 지웠습니다.")?;
+            }
+            Some(id) => match id.parse::<usize>() {
+                Ok(id) => match self.remove(id) {
+                    Some(_) => term.write_line(&format!("{}번 breakpoint를 지웠습니다.", id))?,
+                    None => return report_error(term, &format!("{}번 breakpoint가 없습니다.", id)),
+                },
+                Err(err) => {
+                    let status = report_error(term, "잘못된 입력입니다.")?;
+                    term.write_line(&format!("{}", err))?;
+                    return Ok(status);
+                }
+            },
+        }
+        Ok(Status::Done)
+    }
+
+    pub fn enable_command(
+        &mut self,
+        term: &Term,
+        body: Option<&str>,
+        enabled: bool,
+    ) -> IOResult<Status> {
+        let state = if enabled { "활성화" } else { "비활성화" };
+        match body.map(str::trim) {
+            None | Some("") => {
+                for bp in self.entries.values_mut() {
+                    bp.enabled = enabled;
+                }
+                term.write_line(&format!("모든 breakpoint를 {}했습니다.", state))?;
+            }
+            Some(id) => match id.parse::<usize>() {
+                Ok(id) if self.set_enabled(id, enabled) => {
+                    term.write_line(&format!("{}번 breakpoint를 {}했습니다.", id, state))?
+                }
+                Ok(id) => return report_error(term, &format!("{}번 breakpoint가 없습니다.", id)),
+                Err(err) => {
+                    let status = report_error(term, "잘못된 입력입니다.")?;
+                    term.write_line(&format!("{}", err))?;
+                    return Ok(status);
+                }
+            },
+        }
+        Ok(Status::Done)
+    }
+
+    pub fn list_command(&self, symbol_table: &[TableEntry], term: &Term) -> IOResult<()> {
+        if self.entries.is_empty() {
+            return term.write_line("설정된 breakpoint가 없습니다.");
+        }
+        term.write_line("id    type     enabled addr         hits   symbol")?;
+        for (id, bp) in self.iter() {
+            let (kind, range) = match bp.kind {
+                BreakpointKind::Code => (String::from("break"), format!("x{:04X}", bp.addr)),
+                BreakpointKind::Watch { len, access } => (
+                    format!("watch{}", access.as_str()),
+                    format!("x{:04X}~x{:04X}", bp.addr, bp.addr + len - 1),
+                ),
+            };
+            term.write_line(&format!(
+                "#{:<4} {:<8} {:<7} {:<12} {:<6} {}",
+                id,
+                kind,
+                if bp.enabled { "yes" } else { "no" },
+                range,
+                bp.hits,
+                symbol_table_query(symbol_table, bp.addr)
+            ))?;
+            if let Some(cond) = &bp.condition {
+                term.write_line(&format!("      조건: {}", cond.source))?;
+            }
+            if bp.ignore > 0 {
+                term.write_line(&format!("      앞으로 {}번 무시합니다.", bp.ignore))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::collections::VecDeque;
+use std::io::Result as IOResult;
+
+/// 기억해 둘 잘못된 RET의 최대 개수
+const MAX_MISMATCHES: usize = 8;
+
+/// 서브루틴 호출 하나
+pub struct Frame {
+    /// JSR/JSRR/TRAP instruction의 주소
+    pub caller: u16,
+    /// 호출된 서브루틴의 시작 주소
+    pub target: u16,
+    /// 호출한 instruction의 종류 (JSR, JSRR, TRAP)
+    pub kind: &'static str,
+}
+
+impl Frame {
+    /// RET이 돌아와야 하는 주소
+    pub fn return_addr(&self) -> u16 {
+        self.caller.wrapping_add(1)
+    }
+}
+
+/// 호출한 곳이 아닌 곳으로 돌아간 RET
+pub struct Mismatch {
+    /// RET instruction의 주소
+    pub pc: u16,
+    /// 가장 안쪽 호출이 돌아와야 했던 주소
+    pub expected: u16,
+    /// 실제로 돌아간 주소(RET 당시의 R7)
+    pub actual: u16,
+}
+
+/// 실행한 JSR/JSRR/TRAP과 RET으로 다시 만든 호출 스택.
+/// VM의 메모리에 있는 스택과 관계없이, 디버거가 따로 관리합니다.
+#[derive(Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    mismatches: VecDeque<Mismatch>,
+    /// undo, restore 등 instruction 실행이 아닌 방법으로 VM이 바뀌어 스택이 정확하지 않을 수 있습니다.
+    stale: bool,
+}
+
+impl CallStack {
+    pub fn new() -> CallStack {
+        Default::default()
+    }
+
+    /// 바깥쪽 호출부터 차례로 담긴 frame들
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// 호출 스택이 VM과 맞지 않을 수 있음을 표시합니다.
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+    }
+
+    /// undo 등으로 VM이 바뀌어 호출 스택이 정확하지 않을 수 있는지 여부
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// 호출 스택을 모두 비웁니다. (snapshot load, restore 등)
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.mismatches.clear();
+        self.stale = false;
+    }
+
+    /// pc의 instruction을 실행한 직후에 호출합니다. 지금 vm.pc는 instruction이 옮겨간 곳입니다.
+    pub fn after_step(&mut self, pc: u16, instruction: u16, vm: &VM) {
+        let kind = match Instruction::from_u16(instruction) {
+            Instruction::JSR { .. } => "JSR",
+            Instruction::JSRR { .. } => "JSRR",
+            Instruction::TRAP { .. } => "TRAP",
+            Instruction::JMP { base: 7 } | Instruction::RTI => {
+                self.ret(pc, vm.pc);
+                return;
+            }
+            // R7이 아닌 레지스터로 돌아올 주소에 JMP한 것도 돌아간 것으로 봅니다.
+            Instruction::JMP { .. }
+                if self.frames.last().map(Frame::return_addr) == Some(vm.pc) =>
+            {
+                self.frames.pop();
+                return;
+            }
+            _ => return,
+        };
+        self.frames.push(Frame {
+            caller: pc,
+            target: vm.pc,
+            kind,
+        });
+    }
+
+    fn ret(&mut self, pc: u16, to: u16) {
+        let expected = match self.frames.last() {
+            Some(frame) => frame.return_addr(),
+            // 디버거가 추적을 시작하기 전의 호출에서 돌아가는 경우입니다.
+            None => return,
+        };
+        if expected == to {
+            self.frames.pop();
+            return;
+        }
+
+        if self.mismatches.len() == MAX_MISMATCHES {
+            self.mismatches.pop_front();
+        }
+        self.mismatches.push_back(Mismatch {
+            pc,
+            expected,
+            actual: to,
+        });
+        // 더 바깥쪽 호출로 돌아갔다면 그 호출까지 빠져나온 것으로 봅니다.
+        match self
+            .frames
+            .iter()
+            .rposition(|frame| frame.return_addr() == to)
+        {
+            Some(idx) => self.frames.truncate(idx),
+            None => {
+                self.frames.pop();
+            }
+        }
+    }
+
+    /// backtrace: 현재 위치부터 바깥쪽 호출까지 각 frame을 출력합니다.
+    pub fn backtrace_command(
+        &self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        term: &Term,
+    ) -> IOResult<()> {
+        term.write_line(&format!(
+            "#0  x{:04X} ({})",
+            vm.pc,
+            symbol_table_query(symbol_table, vm.pc as usize)
+        ))?;
+        for (i, frame) in self.frames.iter().rev().enumerate() {
+            term.write_line(&format!(
+                "#{:<2} x{:04X} ({}) <- x{:04X} ({})에서 {}",
+                i + 1,
+                frame.target,
+                symbol_table_query(symbol_table, frame.target as usize),
+                frame.caller,
+                symbol_table_query(symbol_table, frame.caller as usize),
+                frame.kind
+            ))?;
+        }
+        if self.frames.is_empty() {
+            term.write_line("추적된 서브루틴 호출이 없습니다.")?;
+        }
+        for mismatch in self.mismatches.iter() {
+            term.write_line(&format!(
+                "경고: x{:04X} ({})의 RET이 x{:04X} ({})가 아닌 x{:04X} ({})로 돌아갔습니다. R7이 덮어씌워졌을 수 있습니다.",
+                mismatch.pc,
+                symbol_table_query(symbol_table, mismatch.pc as usize),
+                mismatch.expected,
+                symbol_table_query(symbol_table, mismatch.expected as usize),
+                mismatch.actual,
+                symbol_table_query(symbol_table, mismatch.actual as usize)
+            ))?;
+        }
+        if self.stale {
+            term.write_line(
+                "주의: undo 등으로 VM을 되돌린 적이 있어, 호출 스택이 실제와 다를 수 있습니다.",
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::helper::{report_error, Status};
+use crate::symbol::{symbol_table_query, TableEntry};
+use crate::undo::UndoBuffer;
+use console::Term;
+use lc3::vm::VM;
+use std::collections::BTreeMap;
+use std::io::Result as IOResult;
+
+/// 이름을 붙여 저장해 둔 VM의 상태
+pub struct Checkpoint {
+    pub vm: VM,
+    /// 저장할 때까지 실행한 instruction의 수
+    pub executed: usize,
+}
+
+/// 이름으로 찾을 수 있는 checkpoint의 모음.
+/// undo buffer와 달리 개수에 제한이 없고, 같은 checkpoint로 몇 번이든 돌아갈 수 있습니다.
+#[derive(Default)]
+pub struct CheckpointSet {
+    entries: BTreeMap<String, Checkpoint>,
+}
+
+/// checkpoint 명령어가 할 일
+#[derive(Debug, PartialEq)]
+enum Action<'a> {
+    List,
+    Save(&'a str),
+    Delete(&'a str),
+}
+
+fn parse_action(body: &str) -> Result<Action<'_>, String> {
+    match body.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [] => Ok(Action::List),
+        ["delete", name] => Ok(Action::Delete(name)),
+        ["delete", ..] => Err("사용법: checkpoint delete <name>".to_owned()),
+        [name] => Ok(Action::Save(name)),
+        _ => Err("checkpoint 이름에는 공백을 쓸 수 없습니다.".to_owned()),
+    }
+}
+
+impl CheckpointSet {
+    pub fn new() -> CheckpointSet {
+        Default::default()
+    }
+
+    /// checkpoint [<name>|delete <name>]: 현재 상태를 name으로 저장하거나, 지우거나, 목록을 출력합니다.
+    pub fn checkpoint_command(
+        &mut self,
+        vm: &VM,
+        executed: usize,
+        symbol_table: &[TableEntry],
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let name = match parse_action(body.unwrap_or("")) {
+            Ok(Action::List) => {
+                self.list(symbol_table, term)?;
+                return Ok(Status::Done);
+            }
+            Ok(Action::Delete(name)) => {
+                if self.entries.remove(name).is_none() {
+                    return report_error(term, &format!("{} checkpoint가 없습니다.", name));
+                }
+                term.write_line(&format!("{} checkpoint를 지웠습니다.", name))?;
+                return Ok(Status::Done);
+            }
+            Ok(Action::Save(name)) => name,
+            Err(err) => return report_error(term, &err),
+        };
+
+        let replaced = self
+            .entries
+            .insert(
+                name.to_owned(),
+                Checkpoint {
+                    vm: vm.clone(),
+                    executed,
+                },
+            )
+            .is_some();
+        term.write_line(&format!(
+            "{} checkpoint를 {}: PC x{:04X} ({})",
+            name,
+            if replaced {
+                "덮어썼습니다"
+            } else {
+                "저장했습니다"
+            },
+            vm.pc,
+            symbol_table_query(symbol_table, vm.pc as usize)
+        ))?;
+        Ok(Status::Done)
+    }
+
+    /// restore <name>: name checkpoint의 상태로 VM을 되돌립니다.
+    /// 성공하면 VM과 executed를 바꾸고 undo buffer의 기록을 지운 뒤 Status::Done을 반환합니다.
+    pub fn restore_command(
+        &self,
+        vm: &mut VM,
+        executed: &mut usize,
+        undo_buffer: Option<&mut UndoBuffer>,
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let name = body.unwrap_or("").trim();
+        if name.is_empty() {
+            return report_error(term, "사용법: restore <name>");
+        }
+        let checkpoint = match self.entries.get(name) {
+            Some(checkpoint) => checkpoint,
+            None => return report_error(term, &format!("{} checkpoint가 없습니다.", name)),
+        };
+        *vm = checkpoint.vm.clone();
+        *executed = checkpoint.executed;
+        if let Some(buffer) = undo_buffer {
+            buffer.clear();
+        }
+        term.write_line(&format!("{} checkpoint로 돌아갔습니다.", name))?;
+        Ok(Status::Done)
+    }
+
+    fn list(&self, symbol_table: &[TableEntry], term: &Term) -> IOResult<()> {
+        if self.entries.is_empty() {
+            return term.write_line("저장된 checkpoint가 없습니다.");
+        }
+        term.write_line("name             pc     instructions symbol")?;
+        for (name, checkpoint) in self.entries.iter() {
+            term.write_line(&format!(
+                "{:<16} x{:04X}  {:<12} {}",
+                name,
+                checkpoint.vm.pc,
+                checkpoint.executed,
+                symbol_table_query(symbol_table, checkpoint.vm.pc as usize)
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delete_needs_a_name() {
+        for body in &["delete", " delete ", "delete a b"] {
+            assert!(parse_action(body).is_err(), "{:?}", body);
+        }
+        assert_eq!(parse_action(" delete  a "), Ok(Action::Delete("a")));
+        assert_eq!(
+            parse_action("delete"),
+            Err("사용법: checkpoint delete <name>".to_owned())
+        );
+    }
+
+    #[test]
+    fn names_cannot_contain_spaces() {
+        assert_eq!(parse_action(""), Ok(Action::List));
+        assert_eq!(parse_action(" loop "), Ok(Action::Save("loop")));
+        assert!(parse_action("a b").is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::asm::Assembly;
+use crate::helper::{format_mem_entry, report_error, Status};
+use crate::source::SourceMap;
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::fs;
+use std::io::{ErrorKind, Result as IOResult};
+
+/// 커버리지 파일의 첫 줄
+const HEADER: &str = "lc3dbg-coverage 1";
+
+/// 사용자 프로그램이 쓸 수 있는 메모리의 시작 주소. 그 앞의 운영체제 코드는 세지 않습니다.
+const USER_SPACE: usize = 0x3000;
+
+/// 조건이 nzp 중 일부인 BR. 항상 또는 절대 분기하지 않는 BR은 방향이 하나뿐이므로 세지 않습니다.
+fn is_conditional_branch(instruction: u16) -> bool {
+    match Instruction::from_u16(instruction) {
+        Instruction::BR { cond, .. } => {
+            let set = [cond.n, cond.z, cond.p].iter().filter(|&&c| c).count();
+            set == 1 || set == 2
+        }
+        _ => false,
+    }
+}
+
+/// .asm 파일 없이 오브젝트 파일을 불러왔을 때 요약에 덧붙이는 안내
+const DATA_NOTE: &str =
+    "참고: .asm 파일 없이 불러온 오브젝트 파일은 데이터도 instruction으로 셉니다. 같은 이름의 .asm 파일을 두면 데이터를 제외합니다.";
+
+/// 커버리지 요약
+struct Summary {
+    covered: usize,
+    instructions: usize,
+    branch_covered: usize,
+    branches: usize,
+}
+
+fn percent(n: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        n as f64 * 100.0 / total as f64
+    }
+}
+
+impl Summary {
+    fn describe(&self) -> String {
+        format!(
+            "instruction {}/{} ({:.2}%), 분기 방향 {}/{} ({:.2}%)",
+            self.covered,
+            self.instructions,
+            percent(self.covered, self.instructions),
+            self.branch_covered,
+            self.branches,
+            percent(self.branch_covered, self.branches)
+        )
+    }
+}
+
+/// 불러온 프로그램의 instruction마다 실행 횟수를, 조건 분기마다 분기한/하지 않은 횟수를 기록합니다.
+pub struct Coverage {
+    pub enabled: bool,
+    /// 커버리지를 계산할 주소(불러온 프로그램의 instruction)
+    program: Vec<bool>,
+    /// 오브젝트 파일로만 불러와 instruction인지 데이터인지 알 수 없는 주소
+    unknown_kind: Vec<bool>,
+    executed: Vec<u64>,
+    taken: Vec<u64>,
+    not_taken: Vec<u64>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage {
+            enabled: false,
+            program: vec![false; 65536],
+            unknown_kind: vec![false; 65536],
+            executed: vec![0; 65536],
+            taken: vec![0; 65536],
+            not_taken: vec![0; 65536],
+        }
+    }
+
+    /// 기록만 지웁니다. 어디에 프로그램을 불러왔는지는 남겨 둡니다.
+    pub fn clear(&mut self) {
+        for counts in [&mut self.executed, &mut self.taken, &mut self.not_taken].iter_mut() {
+            for count in counts.iter_mut() {
+                *count = 0;
+            }
+        }
+    }
+
+    /// 오브젝트 파일(첫 word는 시작 주소)이 차지하는 메모리를 프로그램으로 기록합니다.
+    /// 데이터를 구분할 수 없으므로, 이어서 load_assembly를 부르지 않으면 데이터도 instruction으로 셉니다.
+    pub fn load_object(&mut self, object: &[u8]) {
+        if object.len() < 2 {
+            return;
+        }
+        let orig = (usize::from(object[0]) << 8) + usize::from(object[1]);
+        let end = (orig + (object.len() - 2) / 2).min(self.program.len());
+        let range = orig.max(USER_SPACE)..end.max(USER_SPACE);
+        for word in self.program[range.clone()].iter_mut() {
+            *word = true;
+        }
+        for word in self.unknown_kind[range].iter_mut() {
+            *word = true;
+        }
+    }
+
+    /// 어셈블 결과 중 instruction만 프로그램으로 기록합니다. (데이터는 세지 않습니다.)
+    pub fn load_assembly(&mut self, assembly: &Assembly) {
+        for section in assembly.sections.iter() {
+            let orig = section.orig as usize;
+            for word in self.program[orig..orig + section.words.len()].iter_mut() {
+                *word = orig >= USER_SPACE;
+            }
+            for word in self.unknown_kind[orig..orig + section.words.len()].iter_mut() {
+                *word = false;
+            }
+        }
+        for &addr in assembly.data.iter() {
+            self.program[addr as usize] = false;
+        }
+    }
+
+    /// PC의 instruction을 실행하기 전에 호출합니다.
+    pub fn record(&mut self, vm: &VM) {
+        let pc = vm.pc as usize;
+        self.executed[pc] += 1;
+        if let Instruction::BR { cond, .. } = Instruction::from_u16(vm.mem[pc]) {
+            if cond.satisfies(&vm.condition) {
+                self.taken[pc] += 1;
+            } else {
+                self.not_taken[pc] += 1;
+            }
+        }
+    }
+
+    fn addresses(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.program.len()).filter(move |&addr| self.program[addr])
+    }
+
+    /// 데이터일 수도 있는 word를 instruction으로 세고 있는지 여부
+    fn may_count_data(&self) -> bool {
+        self.addresses().any(|addr| self.unknown_kind[addr])
+    }
+
+    fn summary(&self, vm: &VM) -> Summary {
+        let mut summary = Summary {
+            covered: 0,
+            instructions: 0,
+            branch_covered: 0,
+            branches: 0,
+        };
+        for addr in self.addresses() {
+            summary.instructions += 1;
+            if self.executed[addr] > 0 {
+                summary.covered += 1;
+            }
+            if is_conditional_branch(vm.mem[addr]) {
+                summary.branches += 2;
+                summary.branch_covered +=
+                    (self.taken[addr] > 0) as usize + (self.not_taken[addr] > 0) as usize;
+            }
+        }
+        summary
+    }
+
+    /// 실행되지 않은 instruction과, 한쪽으로만 분기한 BR을 출력합니다.
+    fn report(&self, vm: &VM, symbol_table: &[TableEntry], term: &Term) -> IOResult<()> {
+        let summary = self.summary(vm);
+        if summary.instructions == 0 {
+            return term.write_line("커버리지를 계산할 프로그램이 없습니다.");
+        }
+        term.write_line(&format!("커버리지: {}", summary.describe()))?;
+        if self.may_count_data() {
+            term.write_line(DATA_NOTE)?;
+        }
+
+        // 실행되지 않은 instruction은 연속된 범위로 묶어 출력합니다.
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for addr in self.addresses().filter(|&addr| self.executed[addr] == 0) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == addr => *end = addr,
+                _ => ranges.push((addr, addr)),
+            }
+        }
+        if !ranges.is_empty() {
+            term.write_line("실행되지 않은 instruction:")?;
+        }
+        for &(start, end) in ranges.iter() {
+            if start == end {
+                term.write_line(&format!(
+                    "    x{:04X} ({})",
+                    start,
+                    symbol_table_query(symbol_table, start)
+                ))?;
+            } else {
+                term.write_line(&format!(
+                    "    x{:04X} ({}) - x{:04X} ({}): {}개",
+                    start,
+                    symbol_table_query(symbol_table, start),
+                    end,
+                    symbol_table_query(symbol_table, end),
+                    end - start + 1
+                ))?;
+            }
+        }
+
+        let partial = self
+            .addresses()
+            .filter(|&addr| is_conditional_branch(vm.mem[addr]) && self.executed[addr] > 0)
+            .filter(|&addr| self.taken[addr] == 0 || self.not_taken[addr] == 0)
+            .collect::<Vec<_>>();
+        if !partial.is_empty() {
+            term.write_line("한쪽으로만 실행된 분기:")?;
+        }
+        for addr in partial {
+            term.write_line(&format!(
+                "    x{:04X} ({}) {}: {}",
+                addr,
+                symbol_table_query(symbol_table, addr),
+                Instruction::from_u16(vm.mem[addr]),
+                if self.taken[addr] == 0 {
+                    "분기한 적이 없습니다."
+                } else {
+                    "분기하지 않은 적이 없습니다."
+                }
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// 프로그램의 모든 instruction을 실행 횟수, 분기 횟수와 함께 출력합니다.
+    /// 실행되지 않았거나 한쪽으로만 분기한 줄은 !로 표시합니다.
+    fn list(
+        &self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        source_map: &SourceMap,
+        term: &Term,
+    ) -> IOResult<()> {
+        for addr in self.addresses() {
+            let branch = is_conditional_branch(vm.mem[addr]);
+            let missed = self.executed[addr] == 0
+                || (branch && (self.taken[addr] == 0 || self.not_taken[addr] == 0));
+            let count = if self.executed[addr] == 0 {
+                "-----".to_owned()
+            } else {
+                self.executed[addr].to_string()
+            };
+            let outcome = if branch {
+                format!("T:{} F:{}", self.taken[addr], self.not_taken[addr])
+            } else {
+                String::new()
+            };
+            term.write_line(&format!(
+                "{} {:>8} {:<13} {}",
+                if missed { "!" } else { " " },
+                count,
+                outcome,
+                format_mem_entry(addr, vm, symbol_table, source_map)
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// 다른 실행과 합칠 수 있는 형식으로 저장합니다.
+    /// 첫 줄은 HEADER, #으로 시작하는 줄은 요약, 나머지는 `주소 실행 분기 비분기` 한 줄씩입니다.
+    pub fn save(&self, vm: &VM, path: &str) -> IOResult<()> {
+        let mut text = format!("{}\n# {}\n", HEADER, self.summary(vm).describe());
+        for addr in self.addresses() {
+            text += &format!(
+                "x{:04X} {} {} {}\n",
+                addr, self.executed[addr], self.taken[addr], self.not_taken[addr]
+            );
+        }
+        fs::write(path, text)
+    }
+
+    /// 저장된 커버리지를 현재 기록에 더합니다.
+    pub fn merge(&mut self, path: &str) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(format!("{}: 커버리지 파일이 아닙니다.", path));
+        }
+        let mut entries: Vec<(usize, u64, u64, u64)> = vec![];
+        for (idx, line) in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let entry = match fields.as_slice() {
+                [addr, executed, taken, not_taken] => {
+                    let addr = addr
+                        .strip_prefix('x')
+                        .and_then(|addr| usize::from_str_radix(addr, 16).ok())
+                        .filter(|&addr| addr < self.program.len());
+                    match (addr, executed.parse(), taken.parse(), not_taken.parse()) {
+                        (Some(addr), Ok(executed), Ok(taken), Ok(not_taken)) => {
+                            Some((addr, executed, taken, not_taken))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match entry {
+                Some(entry) => entries.push(entry),
+                None => return Err(format!("{}: {}번째 줄이 잘못되었습니다.", path, idx + 1)),
+            }
+        }
+        for (addr, executed, taken, not_taken) in entries {
+            self.program[addr] = true;
+            self.executed[addr] += executed;
+            self.taken[addr] += taken;
+            self.not_taken[addr] += not_taken;
+        }
+        Ok(())
+    }
+
+    /// 파일이 있다면 그 기록을 현재 기록에 더합니다. (--coverage로 시작할 때)
+    pub fn merge_if_exists(&mut self, path: &str, term: &Term) -> IOResult<Status> {
+        match fs::metadata(path) {
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(Status::Done),
+            _ => match self.merge(path) {
+                Ok(()) => {
+                    term.write_line(&format!("커버리지 기록을 이어서 씁니다: {}", path))?;
+                    Ok(Status::Done)
+                }
+                Err(err) => {
+                    report_error(term, &format!("커버리지 파일을 합치지 못했습니다: {}", err))
+                }
+            },
+        }
+    }
+
+    /// 기록을 저장하고 요약을 출력합니다. (--coverage로 종료할 때)
+    pub fn save_and_report(&self, vm: &VM, path: &str, term: &Term) -> IOResult<Status> {
+        match self.save(vm, path) {
+            Ok(()) => {
+                term.write_line(&format!(
+                    "커버리지를 저장했습니다: {}: {}",
+                    path,
+                    self.summary(vm).describe()
+                ))?;
+                if self.may_count_data() {
+                    term.write_line(DATA_NOTE)?;
+                }
+                Ok(Status::Done)
+            }
+            Err(err) => report_error(
+                term,
+                &format!("커버리지 파일을 저장하지 못했습니다: {}: {}", path, err),
+            ),
+        }
+    }
+
+    /// coverage on|off|clear|report|list|save <file>|merge <file>
+    pub fn coverage_command(
+        &mut self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        source_map: &SourceMap,
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let args = body.unwrap_or("").split_whitespace().collect::<Vec<_>>();
+        match args.as_slice() {
+            [] => term.write_line(&format!(
+                "커버리지 기록이 {}. {}",
+                if self.enabled {
+                    "켜져 있습니다"
+                } else {
+                    "꺼져 있습니다"
+                },
+                self.summary(vm).describe()
+            ))?,
+            ["on"] => {
+                self.enabled = true;
+                term.write_line("실행하는 instruction과 분기 방향을 기록합니다.")?
+            }
+            ["off"] => {
+                self.enabled = false;
+                term.write_line("커버리지를 기록하지 않습니다.")?
+            }
+            ["clear"] => {
+                self.clear();
+                term.write_line("커버리지 기록을 지웠습니다.")?
+            }
+            ["report"] => self.report(vm, symbol_table, term)?,
+            ["list"] => self.list(vm, symbol_table, source_map, term)?,
+            ["save", path] => return self.save_and_report(vm, path, term),
+            ["merge", path] => match self.merge(path) {
+                Ok(()) => term.write_line(&format!(
+                    "커버리지를 합쳤습니다: {}",
+                    self.summary(vm).describe()
+                ))?,
+                Err(err) => {
+                    return report_error(
+                        term,
+                        &format!("커버리지 파일을 합치지 못했습니다: {}", err),
+                    )
+                }
+            },
+            _ => {
+                return report_error(
+                    term,
+                    "사용법: coverage [on|off|clear|report|list|save <file>|merge <file>]",
+                )
+            }
+        }
+        Ok(Status::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn object_without_assembly_may_count_data() {
+        let assembly = match assemble(".ORIG x3000\nHALT\nDATA .FILL #5\n.END\n") {
+            Ok(assembly) => assembly,
+            Err(_) => panic!("어셈블하지 못했습니다."),
+        };
+        let mut coverage = Coverage::new();
+        coverage.load_object(&assembly.sections[0].to_object());
+        assert!(coverage.may_count_data());
+        assert_eq!(
+            coverage.addresses().collect::<Vec<_>>(),
+            vec![0x3000, 0x3001]
+        );
+
+        coverage.load_assembly(&assembly);
+        assert!(!coverage.may_count_data());
+        assert_eq!(coverage.addresses().collect::<Vec<_>>(), vec![0x3000]);
+    }
+}
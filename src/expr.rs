@@ -0,0 +1,434 @@
+use crate::helper::parse_usize_with_prefix;
+use crate::symbol::{symbol_table_lookup, TableEntry};
+use lc3::vm::VM;
+
+/// breakpoint 조건식에 쓰이는 식.
+/// 모든 값은 i32로 계산하며, 레지스터/메모리/16비트 범위의 숫자는 부호 있는 16비트 값으로 해석합니다.
+/// (예: xFFFF는 -1과 같습니다.)
+pub enum Expr {
+    Number(i32),
+    Register(usize),
+    Pc,
+    Ir,
+    Psr,
+    /// cc == n/z/p 형태의 비교. 현재 CC가 주어진 플래그 중 하나와 일치하면 참입니다.
+    CcMatches {
+        mask: u16,
+        negate: bool,
+    },
+    Mem(Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Clone)]
+enum Token {
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "!", "(", ")", "[", "]", "#",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(format!("알 수 없는 문자입니다: {}", rest));
+            }
+            tokens.push(Token::Ident(rest[..len].to_owned()));
+            rest = &rest[len..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// 16비트 값을 부호 있는 값으로 해석합니다.
+fn signed(value: usize) -> i32 {
+    i32::from(value as u16 as i16)
+}
+
+/// n, z, p 문자로 이루어진 문자열을 PSR의 CC 비트 마스크로 바꿉니다.
+pub fn cc_mask(flags: &str) -> Option<u16> {
+    let mut mask = 0;
+    for c in flags.chars() {
+        mask |= match c.to_ascii_lowercase() {
+            'n' => 0b100,
+            'z' => 0b010,
+            'p' => 0b001,
+            _ => return None,
+        };
+    }
+    if mask == 0 {
+        None
+    } else {
+        Some(mask)
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    symbol_table: &'a [TableEntry],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(format!("'{}'가 필요합니다.", op))
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut lhs = next(self)?;
+        'outer: loop {
+            for (token, op) in ops {
+                if self.eat(token) {
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(next(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        // cc == nz 처럼 CC를 플래그와 비교하는 경우
+        if let Some(Token::Ident(name)) = self.peek() {
+            if name.eq_ignore_ascii_case("cc") {
+                self.pos += 1;
+                let negate = if self.eat("==") {
+                    false
+                } else if self.eat("!=") {
+                    true
+                } else {
+                    return Err(String::from("cc는 == 또는 != 로만 비교할 수 있습니다."));
+                };
+                return match self.peek().cloned() {
+                    Some(Token::Ident(flags)) => match cc_mask(&flags) {
+                        Some(mask) => {
+                            self.pos += 1;
+                            Ok(Expr::CcMatches { mask, negate })
+                        }
+                        None => Err(format!("잘못된 CC 플래그입니다: {}", flags)),
+                    },
+                    _ => Err(String::from("cc와 비교할 플래그(n, z, p)가 필요합니다.")),
+                };
+            }
+        }
+
+        let lhs = self.sum()?;
+        for (token, op) in &[
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ] {
+            if self.eat(token) {
+                return Ok(Expr::Binary(*op, Box::new(lhs), Box::new(self.sum()?)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary(&[("*", BinaryOp::Mul)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+        } else if self.eat("!") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        if self.eat("(") {
+            let expr = self.or()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if self.eat("#") {
+            let negative = self.eat("-");
+            return match self.peek().cloned() {
+                Some(Token::Ident(ref digits)) => match digits.parse::<i32>() {
+                    Ok(n) => {
+                        self.pos += 1;
+                        Ok(Expr::Number(if negative { -n } else { n }))
+                    }
+                    Err(_) => Err(format!("잘못된 10진수입니다: #{}", digits)),
+                },
+                _ => Err(String::from("# 뒤에 10진수가 필요합니다.")),
+            };
+        }
+        let name = match self.peek().cloned() {
+            Some(Token::Ident(name)) => name,
+            Some(Token::Op(op)) => return Err(format!("예상하지 못한 '{}'입니다.", op)),
+            None => return Err(String::from("식이 끝나지 않았습니다.")),
+        };
+        self.pos += 1;
+
+        let lower = name.to_ascii_lowercase();
+        match lower.as_str() {
+            "pc" => return Ok(Expr::Pc),
+            "ir" => return Ok(Expr::Ir),
+            "psr" => return Ok(Expr::Psr),
+            "mem" => {
+                self.expect("[")?;
+                let addr = self.or()?;
+                self.expect("]")?;
+                return Ok(Expr::Mem(Box::new(addr)));
+            }
+            _ => (),
+        }
+        if lower.len() == 2 && lower.starts_with('r') {
+            if let Ok(n @ 0..=7) = lower[1..].parse::<usize>() {
+                return Ok(Expr::Register(n));
+            }
+        }
+        if let Ok(n) = parse_usize_with_prefix(&name) {
+            return if n < 1 << 16 {
+                Ok(Expr::Number(signed(n)))
+            } else {
+                Err(format!("16비트 범위를 벗어나는 숫자입니다: {}", name))
+            };
+        }
+        match symbol_table_lookup(self.symbol_table, &name) {
+            Some(addr) => Ok(Expr::Number(signed(addr))),
+            None => Err(format!("존재하지 않는 심볼입니다: {}", name)),
+        }
+    }
+}
+
+impl Expr {
+    /// 식을 파싱합니다. 심볼 이름은 파싱할 때 주소로 바뀝니다.
+    pub fn parse(s: &str, symbol_table: &[TableEntry]) -> Result<Expr, String> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            symbol_table,
+        };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(String::from("식의 끝에 해석할 수 없는 내용이 있습니다."));
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, vm: &VM) -> i32 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Register(n) => i32::from(vm.register[*n]),
+            Expr::Pc => signed(vm.pc as usize),
+            Expr::Ir => signed(vm.ir as usize),
+            Expr::Psr => signed(vm.psr() as usize),
+            Expr::CcMatches { mask, negate } => ((vm.psr() & 0b111 & mask != 0) != *negate) as i32,
+            Expr::Mem(addr) => signed(vm.mem[addr.eval(vm) as u16 as usize] as usize),
+            Expr::Unary(UnaryOp::Neg, e) => e.eval(vm).wrapping_neg(),
+            Expr::Unary(UnaryOp::Not, e) => (e.eval(vm) == 0) as i32,
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(vm);
+                // &&, ||는 short-circuit으로 계산합니다.
+                match op {
+                    BinaryOp::And if lhs == 0 => return 0,
+                    BinaryOp::Or if lhs != 0 => return 1,
+                    _ => (),
+                }
+                let rhs = rhs.eval(vm);
+                match op {
+                    BinaryOp::Add => lhs.wrapping_add(rhs),
+                    BinaryOp::Sub => lhs.wrapping_sub(rhs),
+                    BinaryOp::Mul => lhs.wrapping_mul(rhs),
+                    BinaryOp::Eq => (lhs == rhs) as i32,
+                    BinaryOp::Ne => (lhs != rhs) as i32,
+                    BinaryOp::Lt => (lhs < rhs) as i32,
+                    BinaryOp::Le => (lhs <= rhs) as i32,
+                    BinaryOp::Gt => (lhs > rhs) as i32,
+                    BinaryOp::Ge => (lhs >= rhs) as i32,
+                    BinaryOp::And | BinaryOp::Or => (rhs != 0) as i32,
+                }
+            }
+        }
+    }
+
+    pub fn is_true(&self, vm: &VM) -> bool {
+        self.eval(vm) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols() -> Vec<TableEntry> {
+        let mut table = vec![TableEntry::Unknown; 65536];
+        table[0x3005] = TableEntry::Symbol(String::from("LOOP"));
+        table
+    }
+
+    fn vm() -> VM {
+        let mut vm = VM::new();
+        vm.pc = 0x3002;
+        vm.register[1] = 5;
+        vm.register[2] = -3;
+        vm.mem[0x3005] = 0x1234;
+        vm.mem[0x4000] = 0xFFFF;
+        vm
+    }
+
+    fn eval(s: &str) -> i32 {
+        match Expr::parse(s, &symbols()) {
+            Ok(expr) => expr.eval(&vm()),
+            Err(err) => panic!("{}: {}", s, err),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("10 - 4 - 3"), 3);
+        assert_eq!(eval("1 + 1 == 2"), 1);
+        assert_eq!(eval("1 == 1 && 2 < 1 || 3 >= 3"), 1);
+        assert_eq!(eval("1 == 1 && (2 < 1 || 0)"), 0);
+        assert_eq!(eval("!0 + !5"), 1);
+        assert_eq!(eval("- -4"), 4);
+    }
+
+    #[test]
+    fn literals_are_signed_16_bit() {
+        assert_eq!(eval("#-5"), -5);
+        assert_eq!(eval("#12"), 12);
+        assert_eq!(eval("x10"), 16);
+        assert_eq!(eval("xFFFF"), -1);
+        assert_eq!(eval("xFFFF == #-1"), 1);
+        assert_eq!(eval("x7FFF"), 32767);
+        assert_eq!(eval("-x8000"), 32768);
+    }
+
+    #[test]
+    fn registers_and_memory() {
+        assert_eq!(eval("R1"), 5);
+        assert_eq!(eval("r2 < 0"), 1);
+        assert_eq!(eval("R1 + r2"), 2);
+        assert_eq!(eval("PC"), 0x3002);
+        assert_eq!(eval("mem[LOOP]"), 0x1234);
+        assert_eq!(eval("mem[PC + 3]"), 0x1234);
+        assert_eq!(eval("MEM[x4000]"), -1);
+        assert_eq!(eval("LOOP"), 0x3005);
+        assert_eq!(eval("loop"), 0x3005);
+    }
+
+    #[test]
+    fn cc_comparison() {
+        let symbols = symbols();
+        let mut vm = vm();
+        vm.condition.n = false;
+        vm.condition.z = true;
+        vm.condition.p = false;
+        let check = |s: &str, vm: &VM| Expr::parse(s, &symbols).unwrap().is_true(vm);
+        assert!(check("cc == z", &vm));
+        assert!(check("CC == nz", &vm));
+        assert!(!check("cc == p", &vm));
+        assert!(check("cc != np", &vm));
+        assert!(check("cc == z && R1 == 5", &vm));
+        vm.condition.z = false;
+        vm.condition.p = true;
+        assert!(check("cc == zp", &vm));
+        assert!(!check("cc != p", &vm));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        let symbols = symbols();
+        for s in &[
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 + 2)",
+            "mem[x3000",
+            "mem x3000",
+            "R8",
+            "NOSUCH",
+            "x10000",
+            "#",
+            "#abc",
+            "cc < z",
+            "cc == q",
+            "cc ==",
+            "1 2",
+            "R1 @ 2",
+            "가나 == 1",
+            "==",
+        ] {
+            assert!(Expr::parse(s, &symbols).is_err(), "{:?}", s);
+        }
+    }
+}
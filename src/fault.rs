@@ -0,0 +1,191 @@
+use crate::access::MemoryAccess;
+use crate::asm::Assembly;
+use crate::helper::{report_error, Status};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Result as IOResult;
+
+/// 사용자 프로그램이 쓸 수 있는 메모리의 시작 주소. 그 앞은 운영체제의 영역입니다.
+const USER_SPACE: usize = 0x3000;
+/// 장치 레지스터 영역의 시작 주소
+const DEVICE_SPACE: usize = 0xFE00;
+
+/// 프로그램을 불러올 때 메모리 word마다 기록해 둔 종류
+#[derive(Clone, Copy, PartialEq)]
+enum WordKind {
+    /// 어떤 프로그램도 불러오지 않은 곳
+    Uninitialized,
+    /// 어셈블러가 instruction으로 만든 곳
+    Code,
+    /// .FILL/.BLKW/.STRINGZ로 만든 곳
+    Data,
+    /// 오브젝트 파일 등에서 불러왔지만 코드인지 데이터인지 모르는 곳
+    Loaded,
+}
+
+/// 실행을 멈춰야 하는 LC-3 실행 오류
+#[derive(PartialEq)]
+pub enum Fault {
+    /// 예약된 opcode(1101)
+    IllegalOpcode,
+    /// 사용자 모드에서의 RTI
+    RtiInUserMode,
+    /// lc3-rs는 RTI를 실행하지 못합니다.
+    RtiUnsupported,
+    /// 사용자 모드에서 운영체제 영역이나 장치 레지스터에 접근
+    PrivilegeViolation { addr: usize, write: bool },
+    /// .FILL/.STRINGZ 등 데이터를 실행
+    ExecuteData,
+    /// 불러온 프로그램이 없는 메모리를 실행
+    ExecuteUninitialized,
+    /// 불러온 프로그램의 끝을 지나 실행
+    PastImage,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Fault::IllegalOpcode => write!(f, "예약된 opcode(1101)를 실행하려고 합니다."),
+            Fault::RtiInUserMode => write!(
+                f,
+                "사용자 모드에서 RTI를 실행하려고 합니다. (privilege mode violation)"
+            ),
+            Fault::RtiUnsupported => write!(f, "RTI는 이 VM에서 지원하지 않습니다."),
+            Fault::PrivilegeViolation { addr, write } => write!(
+                f,
+                "사용자 모드에서 {} x{:04X}{} (access control violation)",
+                if *addr >= DEVICE_SPACE {
+                    "장치 레지스터"
+                } else {
+                    "운영체제 영역"
+                },
+                addr,
+                if *write {
+                    "에 쓰려고 합니다."
+                } else {
+                    "을(를) 읽으려고 합니다."
+                }
+            ),
+            Fault::ExecuteData => write!(
+                f,
+                "데이터(.FILL/.BLKW/.STRINGZ)를 instruction으로 실행하려고 합니다."
+            ),
+            Fault::ExecuteUninitialized => {
+                write!(f, "프로그램을 불러오지 않은 메모리를 실행하려고 합니다.")
+            }
+            Fault::PastImage => write!(f, "불러온 프로그램의 끝을 지나 실행하려고 합니다."),
+        }
+    }
+}
+
+/// 실행하기 전에 instruction을 검사해 실행 오류를 찾습니다.
+pub struct FaultDetector {
+    map: Vec<WordKind>,
+    /// false라면 예약된 opcode와 RTI만 검사합니다. (둘은 VM이 실행할 수 없습니다.)
+    pub enabled: bool,
+}
+
+impl Default for FaultDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultDetector {
+    /// 운영체제 영역만 불러온 상태로 만듭니다.
+    pub fn new() -> FaultDetector {
+        let mut map = vec![WordKind::Uninitialized; 65536];
+        for kind in map[..USER_SPACE].iter_mut() {
+            *kind = WordKind::Loaded;
+        }
+        FaultDetector { map, enabled: true }
+    }
+
+    /// 오브젝트 파일(첫 word는 시작 주소)이 차지하는 메모리를 기록합니다.
+    pub fn load_object(&mut self, object: &[u8]) {
+        if object.len() < 2 {
+            return;
+        }
+        let orig = (usize::from(object[0]) << 8) + usize::from(object[1]);
+        let end = (orig + (object.len() - 2) / 2).min(self.map.len());
+        for kind in self.map[orig..end].iter_mut() {
+            *kind = WordKind::Loaded;
+        }
+    }
+
+    /// 어셈블 결과에서 코드와 데이터를 구분해 기록합니다.
+    pub fn load_assembly(&mut self, assembly: &Assembly) {
+        for section in assembly.sections.iter() {
+            let orig = section.orig as usize;
+            for kind in self.map[orig..orig + section.words.len()].iter_mut() {
+                *kind = WordKind::Code;
+            }
+        }
+        for &addr in assembly.data.iter() {
+            self.map[addr as usize] = WordKind::Data;
+        }
+    }
+
+    /// 어디에 무엇을 불러왔는지 모르게 되었을 때(snapshot load) 모든 메모리를 불러온 것으로 봅니다.
+    pub fn forget_images(&mut self) {
+        for kind in self.map.iter_mut() {
+            *kind = WordKind::Loaded;
+        }
+    }
+
+    /// PC의 instruction을 실행하기 전에 호출합니다. access는 그 instruction이 접근할 메모리입니다.
+    pub fn check(&self, vm: &VM, access: &MemoryAccess) -> Option<Fault> {
+        let pc = vm.pc as usize;
+        match Instruction::from_u16(vm.mem[pc]) {
+            Instruction::RESERVED => return Some(Fault::IllegalOpcode),
+            Instruction::RTI if !vm.supervisor => return Some(Fault::RtiInUserMode),
+            Instruction::RTI => return Some(Fault::RtiUnsupported),
+            _ => (),
+        }
+        if !self.enabled {
+            return None;
+        }
+
+        match self.map[pc] {
+            WordKind::Data => return Some(Fault::ExecuteData),
+            WordKind::Uninitialized => {
+                return Some(if pc > 0 && self.map[pc - 1] != WordKind::Uninitialized {
+                    Fault::PastImage
+                } else {
+                    Fault::ExecuteUninitialized
+                })
+            }
+            _ => (),
+        }
+
+        // lc3-rs는 TRAP에서 supervisor 모드로 바꾸지 않으므로, 운영체제 영역의 코드는 검사하지 않습니다.
+        if !vm.supervisor && pc >= USER_SPACE {
+            let privileged = |addr: usize| !(USER_SPACE..DEVICE_SPACE).contains(&addr);
+            if let Some(&addr) = access.reads.iter().find(|&&addr| privileged(addr)) {
+                return Some(Fault::PrivilegeViolation { addr, write: false });
+            }
+            if let Some(addr) = access.write.filter(|&addr| privileged(addr)) {
+                return Some(Fault::PrivilegeViolation { addr, write: true });
+            }
+        }
+        None
+    }
+
+    /// fault [on|off]: 실행 오류 검사를 켜거나 끄고, 현재 상태를 출력합니다.
+    pub fn fault_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        match body.map(str::trim) {
+            None | Some("") => (),
+            Some("on") => self.enabled = true,
+            Some("off") => self.enabled = false,
+            Some(_) => return report_error(term, "사용법: fault [on|off]"),
+        }
+        term.write_line(if self.enabled {
+            "실행 오류 검사가 켜져 있습니다."
+        } else {
+            "실행 오류 검사가 꺼져 있습니다. (예약된 opcode와 RTI만 검사합니다.)"
+        })?;
+        Ok(Status::Done)
+    }
+}
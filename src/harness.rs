@@ -0,0 +1,696 @@
+use crate::breakpoint::BreakpointSet;
+use crate::callstack::CallStack;
+use crate::coverage::Coverage;
+use crate::expr::Expr;
+use crate::fault::FaultDetector;
+use crate::loopcheck::LoopDetector;
+use crate::output::OutputBuffer;
+use crate::profile::Profiler;
+use crate::run::{execute, RunContext, RunUntil, Stop};
+use crate::symbol::{symbol_table_query, TableEntry};
+use crate::uninit::UninitTracker;
+use console::Term;
+use failure::Error;
+use lc3::vm::VM;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Result as IOResult;
+
+/// limit이 주어지지 않은 케이스가 실행할 최대 instruction 수
+const DEFAULT_LIMIT: usize = 1_000_000;
+
+/// HALT 시 OS가 출력하는 메시지. 출력을 비교할 때는 제외합니다.
+const HALT_MESSAGE: &str = "\n\n--- halting the LC-3 ---\n\n";
+
+/// 테스트 명세 파일의 오류. line은 1부터 시작하는 줄 번호입니다.
+pub struct SpecError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}번째 줄: {}", self.line, self.message)
+    }
+}
+
+fn spec_error<T>(line: usize, message: String) -> Result<T, SpecError> {
+    Err(SpecError { line, message })
+}
+
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+/// key = value 한 줄
+struct Entry {
+    key: String,
+    value: Value,
+    line: usize,
+}
+
+/// 문자열 안의 escape(\n, \t, \", \\, \uXXXX 등)를 풉니다.
+fn unescape(s: &str, line: usize) -> Result<String, SpecError> {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        result.push(match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('0') => '\0',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => {
+                let hex = chars.by_ref().take(4).collect::<String>();
+                match u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                {
+                    Some(c) if hex.len() == 4 => c,
+                    _ => return spec_error(line, format!("잘못된 escape입니다: \\u{}", hex)),
+                }
+            }
+            Some(c) => return spec_error(line, format!("잘못된 escape입니다: \\{}", c)),
+            None => return spec_error(line, "문자열이 \\로 끝납니다.".to_owned()),
+        });
+    }
+    Ok(result)
+}
+
+/// s가 "로 시작하는 문자열이라면, 닫는 "까지를 풀어서 나머지와 함께 반환합니다.
+fn parse_basic_string(s: &str, line: usize) -> Result<(String, &str), SpecError> {
+    let mut escaped = false;
+    for (idx, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Ok((unescape(&s[1..idx], line)?, &s[idx + 1..])),
+            _ => (),
+        }
+    }
+    spec_error(line, "문자열이 닫히지 않았습니다.".to_owned())
+}
+
+fn parse_integer(s: &str) -> Option<i64> {
+    let s = s.replace('_', "");
+    let (negative, digits) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, &s[..]),
+    };
+    let n = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(oct) = digits.strip_prefix("0o") {
+        i64::from_str_radix(oct, 8)
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        digits.parse::<i64>()
+    }
+    .ok()?;
+    Some(if negative { -n } else { n })
+}
+
+/// 값 뒤에는 공백과 주석만 올 수 있습니다.
+fn expect_end(rest: &str, line: usize) -> Result<(), SpecError> {
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        spec_error(
+            line,
+            format!("값 뒤에 알 수 없는 내용이 있습니다: {}", rest),
+        )
+    }
+}
+
+/// TOML의 일부(주석, [[case]], 문자열/정수/불리언 값)를 읽어 테이블 목록으로 만듭니다.
+/// 첫 테이블은 [[case]] 이전의 최상위 테이블입니다.
+fn parse_tables(source: &str) -> Result<Vec<Vec<Entry>>, SpecError> {
+    let mut tables = vec![vec![]];
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(idx, text)| (idx + 1, text));
+    while let Some((line, text)) = lines.next() {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        if text.starts_with('[') {
+            match text.find(']').map(|idx| text.split_at(idx)) {
+                Some(("[[case", rest)) if rest.starts_with("]]") => {
+                    expect_end(&rest[2..], line)?;
+                    tables.push(vec![]);
+                    continue;
+                }
+                _ => return spec_error(line, format!("지원하지 않는 테이블입니다: {}", text)),
+            }
+        }
+
+        let (key, rest) = if text.starts_with('"') {
+            parse_basic_string(text, line)?
+        } else {
+            let idx = text
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(text.len());
+            (text[..idx].to_owned(), &text[idx..])
+        };
+        let rest = match rest.trim_start().strip_prefix('=') {
+            Some(rest) if !key.is_empty() => rest.trim_start(),
+            _ => return spec_error(line, format!("key = value 형태가 아닙니다: {}", text)),
+        };
+
+        let value = if let Some(first) = rest.strip_prefix("\"\"\"") {
+            // 여러 줄 문자열. 여는 """ 바로 뒤의 줄바꿈은 무시합니다.
+            let mut raw = String::new();
+            let mut current = first;
+            let mut first_line = true;
+            loop {
+                if let Some(idx) = current.find("\"\"\"") {
+                    raw += &current[..idx];
+                    expect_end(&current[idx + 3..], line)?;
+                    break;
+                }
+                if !(first_line && current.is_empty()) {
+                    raw += current;
+                    raw.push('\n');
+                }
+                first_line = false;
+                current = match lines.next() {
+                    Some((_, text)) => text,
+                    None => return spec_error(line, "문자열이 닫히지 않았습니다.".to_owned()),
+                };
+            }
+            Value::Str(unescape(&raw, line)?)
+        } else if rest.starts_with('"') {
+            let (s, rest) = parse_basic_string(rest, line)?;
+            expect_end(rest, line)?;
+            Value::Str(s)
+        } else if let Some(literal) = rest.strip_prefix('\'') {
+            match literal.find('\'') {
+                Some(idx) => {
+                    expect_end(&literal[idx + 1..], line)?;
+                    Value::Str(literal[..idx].to_owned())
+                }
+                None => return spec_error(line, "문자열이 닫히지 않았습니다.".to_owned()),
+            }
+        } else {
+            let (token, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+            expect_end(rest, line)?;
+            match token {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => match parse_integer(token) {
+                    Some(n) => Value::Int(n),
+                    None => return spec_error(line, format!("잘못된 값입니다: {}", token)),
+                },
+            }
+        };
+        tables.last_mut().unwrap().push(Entry { key, value, line });
+    }
+    Ok(tables)
+}
+
+/// 실행이 끝난 후 expr의 값이 expected와 같은지 확인합니다.
+struct Assertion {
+    source: String,
+    expr: Expr,
+    expected: u16,
+}
+
+/// 테스트 케이스 하나. 매번 새로 불러온 VM에서 실행됩니다.
+pub struct TestCase {
+    pub name: String,
+    /// 프로그램에 전달할 입력(pre_input)
+    pub input: Vec<u8>,
+    /// 실행할 최대 instruction 수
+    pub limit: usize,
+    /// 기대하는 콘솔 출력. HALT 시의 OS 메시지는 제외합니다.
+    pub output: Option<String>,
+    /// VM이 멈춰야(HALT) 하는지 여부
+    pub halted: bool,
+    assertions: Vec<Assertion>,
+}
+
+fn expect_str(entry: &Entry) -> Result<&str, SpecError> {
+    match &entry.value {
+        Value::Str(s) => Ok(s),
+        _ => spec_error(
+            entry.line,
+            format!("{}에는 문자열을 주어야 합니다.", entry.key),
+        ),
+    }
+}
+
+fn expect_limit(entry: &Entry) -> Result<usize, SpecError> {
+    match entry.value {
+        Value::Int(n) if n >= 0 => Ok(n as usize),
+        _ => spec_error(
+            entry.line,
+            "limit에는 0 이상의 정수를 주어야 합니다.".to_owned(),
+        ),
+    }
+}
+
+/// 테스트 명세를 읽습니다. [[case]] 이전의 input, limit은 모든 케이스의 기본값이 됩니다.
+/// 케이스의 name, input, limit, output, halted 외의 key는 조건식으로 해석해
+/// 실행 후의 값이 주어진 값과 같은지 확인합니다. (예: R0 = 3, "mem[RESULT]" = -1, "cc == z" = true)
+pub fn parse_spec(source: &str, symbol_table: &[TableEntry]) -> Result<Vec<TestCase>, SpecError> {
+    let mut tables = parse_tables(source)?.into_iter();
+    let mut default_input = vec![];
+    let mut default_limit = DEFAULT_LIMIT;
+    for entry in tables.next().unwrap_or_default() {
+        match entry.key.as_str() {
+            "input" => default_input = expect_str(&entry)?.as_bytes().to_vec(),
+            "limit" => default_limit = expect_limit(&entry)?,
+            _ => {
+                return spec_error(
+                    entry.line,
+                    format!(
+                        "[[case]] 밖에서는 input, limit만 쓸 수 있습니다: {}",
+                        entry.key
+                    ),
+                )
+            }
+        }
+    }
+
+    let mut cases = vec![];
+    for (idx, table) in tables.enumerate() {
+        let mut case = TestCase {
+            name: format!("{}번 케이스", idx + 1),
+            input: default_input.clone(),
+            limit: default_limit,
+            output: None,
+            halted: true,
+            assertions: vec![],
+        };
+        for entry in table {
+            match (entry.key.as_str(), &entry.value) {
+                ("name", _) => case.name = expect_str(&entry)?.to_owned(),
+                ("input", _) => case.input = expect_str(&entry)?.as_bytes().to_vec(),
+                ("limit", _) => case.limit = expect_limit(&entry)?,
+                ("output", _) => case.output = Some(expect_str(&entry)?.to_owned()),
+                ("halted", Value::Bool(b)) => case.halted = *b,
+                ("halted", _) => {
+                    return spec_error(
+                        entry.line,
+                        "halted에는 true/false를 주어야 합니다.".to_owned(),
+                    )
+                }
+                (key, value) => {
+                    let expected = match *value {
+                        Value::Int(n) if n >= i64::from(i16::MIN) && n <= i64::from(u16::MAX) => {
+                            n as u16
+                        }
+                        Value::Bool(b) => b as u16,
+                        _ => {
+                            return spec_error(
+                                entry.line,
+                                format!("{}에는 16비트 정수나 true/false를 주어야 합니다.", key),
+                            )
+                        }
+                    };
+                    let expr = match Expr::parse(key, symbol_table) {
+                        Ok(expr) => expr,
+                        Err(err) => {
+                            return spec_error(
+                                entry.line,
+                                format!("잘못된 식입니다: {}: {}", key, err),
+                            )
+                        }
+                    };
+                    case.assertions.push(Assertion {
+                        source: key.to_owned(),
+                        expr,
+                        expected,
+                    });
+                }
+            }
+        }
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// 케이스를 실행한 결과
+pub struct CaseResult {
+    /// 실행한 instruction의 수
+    pub instructions: usize,
+    /// 실패한 이유들. 비어 있다면 통과입니다.
+    pub failures: Vec<String>,
+}
+
+/// 두 출력을 줄 단위로 비교해 "  " (같음), "- " (기대값에만 있음), "+ " (실제 출력에만 있음)로 표시합니다.
+fn diff_lines(expected: &str, actual: &str) -> Vec<String> {
+    let a = expected.split('\n').collect::<Vec<_>>();
+    let b = actual.split('\n').collect::<Vec<_>>();
+    // lcs[i][j]: a[i..]와 b[j..]의 최장 공통 부분 수열의 길이
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            result.push(format!("  {}", a[i].escape_debug()));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(format!("- {}", a[i].escape_debug()));
+            i += 1;
+        } else {
+            result.push(format!("+ {}", b[j].escape_debug()));
+            j += 1;
+        }
+    }
+    result
+}
+
+/// program을 복사한 VM에서 케이스를 실행하고 결과를 확인합니다.
+/// 디버거의 run과 같이 `run::execute`로 실행하되, 입력은 케이스의 input만 쓰고 breakpoint와 무한 루프 검사는 하지 않습니다.
+/// 프로그램의 출력은 터미널에 보이지 않고 버퍼에만 모입니다.
+/// coverage가 enabled라면 실행한 instruction과 분기 방향을 더합니다.
+pub fn run_case(
+    program: &VM,
+    faults: &FaultDetector,
+    coverage: &mut Coverage,
+    symbol_table: &[TableEntry],
+    case: &TestCase,
+    term: &Term,
+) -> Result<CaseResult, Error> {
+    let mut vm = program.clone();
+    let mut input = case.input.clone();
+    let mut buffer = OutputBuffer::new(false);
+    let mut loop_detector = LoopDetector::new();
+    loop_detector.enabled = false;
+    let ctx = RunContext {
+        pre_input: &mut input,
+        undo_buffer: None,
+        breakpoints: &mut BreakpointSet::new(),
+        symbol_table,
+        output: &mut buffer,
+        interactive: false,
+        trace: None,
+        call_stack: &mut CallStack::new(),
+        faults,
+        uninit: &mut UninitTracker::new(),
+        profiler: &mut Profiler::new(),
+        coverage,
+        loop_detector: &mut loop_detector,
+    };
+    let run = execute(&mut vm, &mut term.clone(), RunUntil::Limit(case.limit), ctx)?;
+
+    let mut failures = vec![];
+    match &run.stop {
+        Stop::Fault(pc, fault) => failures.push(format!(
+            "실행 오류: x{:04X} ({}): {}",
+            pc,
+            symbol_table_query(symbol_table, *pc as usize),
+            fault
+        )),
+        Stop::Halted if !case.halted => {
+            failures.push("VM이 멈추지 않아야 하지만 멈췄습니다.".to_owned())
+        }
+        Stop::Finished if case.halted => failures.push(format!(
+            "최대 instruction 수({})를 넘었지만 VM이 멈추지 않았습니다.",
+            case.limit
+        )),
+        Stop::NeedInput(pc) if case.halted => failures.push(format!(
+            "프로그램이 입력을 기다리지만 남은 입력이 없습니다: x{:04X} ({})",
+            pc,
+            symbol_table_query(symbol_table, *pc as usize)
+        )),
+        Stop::Paused(message) | Stop::Watch(message) | Stop::Error(message) => {
+            failures.push(message.clone())
+        }
+        _ => (),
+    }
+
+    if let Some(expected) = &case.output {
+        let output = buffer.text();
+        let output = if let Stop::Halted = run.stop {
+            output.strip_suffix(HALT_MESSAGE).unwrap_or(&output)
+        } else {
+            &output
+        };
+        if output != expected {
+            failures.push("출력이 다릅니다:".to_owned());
+            for line in diff_lines(expected, output) {
+                failures.push(format!("    {}", line));
+            }
+        }
+    }
+
+    for assertion in case.assertions.iter() {
+        let actual = assertion.expr.eval(&vm) as u16;
+        if actual != assertion.expected {
+            failures.push(format!(
+                "{}: 기대값 x{:04X} ({}), 실제값 x{:04X} ({})",
+                assertion.source,
+                assertion.expected,
+                assertion.expected as i16,
+                actual,
+                actual as i16
+            ));
+        }
+    }
+
+    Ok(CaseResult {
+        instructions: run.instructions,
+        failures,
+    })
+}
+
+/// lc3dbg test <spec> <program...>: 명세의 각 케이스를 실행하고 결과를 출력합니다.
+/// 모든 케이스가 통과했다면 true를 반환합니다.
+pub fn test_command(
+    program: &VM,
+    faults: &FaultDetector,
+    coverage: &mut Coverage,
+    symbol_table: &[TableEntry],
+    term: &Term,
+    spec_path: &str,
+) -> IOResult<bool> {
+    let source = match std::fs::read_to_string(spec_path) {
+        Ok(source) => source,
+        Err(err) => {
+            term.write_line(&format!(
+                "테스트 명세를 열 수 없습니다: {}: {}",
+                spec_path, err
+            ))?;
+            return Ok(false);
+        }
+    };
+    let cases = match parse_spec(&source, symbol_table) {
+        Ok(cases) => cases,
+        Err(err) => {
+            term.write_line(&format!("{}: {}", spec_path, err))?;
+            return Ok(false);
+        }
+    };
+
+    let mut passed = 0;
+    for case in cases.iter() {
+        let result = match run_case(program, faults, coverage, symbol_table, case, term) {
+            Ok(result) => result,
+            Err(err) => {
+                term.write_line(&format!("[FAIL] {}", case.name))?;
+                term.write_line(&format!("    실행하지 못했습니다: {}", err))?;
+                continue;
+            }
+        };
+        if result.failures.is_empty() {
+            passed += 1;
+            term.write_line(&format!(
+                "[PASS] {} ({} instructions)",
+                case.name, result.instructions
+            ))?;
+        } else {
+            term.write_line(&format!(
+                "[FAIL] {} ({} instructions)",
+                case.name, result.instructions
+            ))?;
+            for failure in result.failures.iter() {
+                term.write_line(&format!("    {}", failure))?;
+            }
+        }
+    }
+    term.write_line(&format!(
+        "결과: {}개 중 {}개 통과, {}개 실패",
+        cases.len(),
+        passed,
+        cases.len() - passed
+    ))?;
+    Ok(passed == cases.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    fn symbols() -> Vec<TableEntry> {
+        let mut table = vec![TableEntry::Unknown; 65536];
+        table[0x3010] = TableEntry::Symbol(String::from("RESULT"));
+        table
+    }
+
+    fn spec_error_line(source: &str) -> usize {
+        match parse_spec(source, &symbols()) {
+            Ok(_) => panic!("오류 없이 읽었습니다: {}", source),
+            Err(err) => err.line,
+        }
+    }
+
+    #[test]
+    fn spec_defaults_and_cases() {
+        let source = r#"
+# 기본값
+input = "ab"
+limit = 1_000
+
+[[case]]
+name = "first"  # 주석
+output = "x\ty\n"
+R0 = 0x41
+"mem[RESULT]" = -1
+"cc == z" = true
+
+[[case]]
+input = 'C:\raw'
+limit = 0b101
+halted = false
+output = """
+line 1
+line 2\u0021"""
+"#;
+        let cases = parse_spec(source, &symbols()).ok().unwrap();
+        assert_eq!(cases.len(), 2);
+
+        assert_eq!(cases[0].name, "first");
+        assert_eq!(cases[0].input, b"ab");
+        assert_eq!(cases[0].limit, 1000);
+        assert_eq!(cases[0].output.as_deref(), Some("x\ty\n"));
+        assert!(cases[0].halted);
+        let assertions = cases[0]
+            .assertions
+            .iter()
+            .map(|a| (a.source.as_str(), a.expected))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            assertions,
+            vec![("R0", 0x41), ("mem[RESULT]", 0xFFFF), ("cc == z", 1)]
+        );
+
+        assert_eq!(cases[1].name, "2번 케이스");
+        assert_eq!(cases[1].input, br"C:\raw");
+        assert_eq!(cases[1].limit, 5);
+        assert!(!cases[1].halted);
+        assert_eq!(cases[1].output.as_deref(), Some("line 1\nline 2!"));
+    }
+
+    #[test]
+    fn spec_errors_report_line() {
+        assert_eq!(spec_error_line("[case]\n"), 1);
+        assert_eq!(spec_error_line("[[case]]\nname\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\nname = \"open\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\noutput = \"\"\"\nopen\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\nname = \"a\\q\"\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\nname = \"a\" b\n"), 2);
+        assert_eq!(spec_error_line("\n\nR0 = 1\n"), 3);
+        assert_eq!(spec_error_line("limit = -1\n"), 1);
+        assert_eq!(spec_error_line("[[case]]\nhalted = 1\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\nR0 = 70000\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\n\"R9 +\" = 1\n"), 2);
+        assert_eq!(spec_error_line("[[case]]\nname = 가\n"), 2);
+    }
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        assert_eq!(diff_lines("a\nb", "a\nb"), vec!["  a", "  b"]);
+        assert_eq!(diff_lines("a\nb\nc", "a\nc"), vec!["  a", "- b", "  c"]);
+        assert_eq!(diff_lines("a\nc", "a\nb\nc"), vec!["  a", "+ b", "  c"]);
+        assert_eq!(
+            diff_lines("a\nb\nc", "a\nx\nc\nd"),
+            vec!["  a", "- b", "+ x", "  c", "+ d"]
+        );
+        assert_eq!(diff_lines("\t", ""), vec!["- \\t", "+ "]);
+    }
+
+    #[test]
+    fn run_case_checks_results() {
+        let assembly = assemble(
+            ".ORIG x3000
+        GETC
+        OUT
+        ST R0, RESULT
+        HALT
+        .BLKW 12
+RESULT  .FILL 0
+        .END",
+        )
+        .ok()
+        .unwrap();
+        let mut program = VM::new();
+        assembly.load(&mut program);
+        let mut faults = FaultDetector::new();
+        faults.load_assembly(&assembly);
+        let mut coverage = Coverage::new();
+        let spec = r#"
+[[case]]
+input = "A"
+output = "A"
+"mem[RESULT]" = 0x41
+
+[[case]]
+input = "B"
+output = "A"
+"mem[RESULT]" = 0x41
+
+[[case]]
+
+[[case]]
+input = "A"
+limit = 3
+"#;
+        let symbol_table = symbols();
+        let cases = parse_spec(spec, &symbol_table).ok().unwrap();
+        let term = Term::stdout();
+        let results = cases
+            .iter()
+            .map(|case| {
+                run_case(&program, &faults, &mut coverage, &symbol_table, case, &term)
+                    .ok()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(results[0].failures.is_empty(), "{:?}", results[0].failures);
+        assert_eq!(results[1].failures.len(), 4);
+        assert_eq!(results[1].failures[0], "출력이 다릅니다:");
+        assert!(results[1].failures[3].starts_with("mem[RESULT]"));
+        assert_eq!(results[2].failures.len(), 1);
+        assert!(results[2].failures[0].contains("입력을 기다리지만"));
+        assert_eq!(results[3].instructions, 3);
+        assert_eq!(results[3].failures.len(), 1);
+        assert!(results[3].failures[0].contains("최대 instruction 수(3)"));
+    }
+}
@@ -1,10 +1,27 @@
-use crate::symbol::{decorate_instruction_str, TableEntry};
+use crate::source::SourceMap;
+use crate::symbol::{
+    decorate_instruction_str, symbol_table_lookup, symbol_table_query, TableEntry,
+};
 use console::Term;
 use lc3::vm::instruction::Instruction;
 use lc3::vm::{MCR, VM};
 use std::io::{Read, Result as IOResult, Write};
 use std::iter::Iterator;
 
+/// 명령어의 실행 결과. 스크립트 모드의 --exit-on-error는 Failed인 명령어가 있으면 종료합니다.
+#[must_use]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    Done,
+    Failed,
+}
+
+/// 명령어가 실패했음을 알리는 메시지를 출력하고 Status::Failed를 반환합니다.
+pub fn report_error(term: &Term, message: &str) -> IOResult<Status> {
+    term.write_line(message)?;
+    Ok(Status::Failed)
+}
+
 pub fn print_register_status(vm: &VM, term: &Term) -> IOResult<()> {
     term.write_line(&format!(
         r#"PC: 0x{:04X}           CC: {}   [{}]
@@ -32,26 +49,98 @@ PC(Next)   : {}
         vm.ir,
         if vm.supervisor { "yes" } else { "no" },
         vm.register[0],
-        vm.register[0] as i16,
+        vm.register[0],
+        vm.register[4],
         vm.register[4],
-        vm.register[4] as i16,
         vm.register[1],
-        vm.register[1] as i16,
+        vm.register[1],
+        vm.register[5],
         vm.register[5],
-        vm.register[5] as i16,
         vm.register[2],
-        vm.register[2] as i16,
+        vm.register[2],
         vm.register[6],
-        vm.register[6] as i16,
+        vm.register[6],
+        vm.register[3],
         vm.register[3],
-        vm.register[3] as i16,
         vm.register[7],
-        vm.register[7] as i16,
+        vm.register[7],
         Instruction::from_u16(vm.ir),
         Instruction::from_u16(vm.mem[vm.pc as usize]),
     ))
 }
 
+/// print_state_diff가 출력할 메모리 변화의 최대 개수
+const MAX_MEMORY_DIFF: usize = 16;
+
+fn cc_str(vm: &VM) -> &'static str {
+    if vm.condition.n {
+        "n"
+    } else if vm.condition.z {
+        "z"
+    } else {
+        "p"
+    }
+}
+
+/// 두 VM 상태 사이에 바뀐 레지스터, PC, CC, 메모리 값을 출력합니다.
+pub fn print_state_diff(
+    old: &VM,
+    new: &VM,
+    symbol_table: &[TableEntry],
+    term: &Term,
+) -> IOResult<()> {
+    let mut changed = false;
+    if old.pc != new.pc {
+        changed = true;
+        term.write_line(&format!(
+            "PC: x{:04X} -> x{:04X} ({})",
+            old.pc,
+            new.pc,
+            symbol_table_query(symbol_table, new.pc as usize)
+        ))?;
+    }
+    for (n, (a, b)) in old.register.iter().zip(new.register.iter()).enumerate() {
+        if a != b {
+            changed = true;
+            term.write_line(&format!("r{}: x{:04X} -> x{:04X} ({})", n, a, b, b))?;
+        }
+    }
+    if cc_str(old) != cc_str(new) {
+        changed = true;
+        term.write_line(&format!("CC: {} -> {}", cc_str(old), cc_str(new)))?;
+    }
+    if old.supervisor != new.supervisor {
+        changed = true;
+        term.write_line(&format!(
+            "Supervisor: {} -> {}",
+            if old.supervisor { "yes" } else { "no" },
+            if new.supervisor { "yes" } else { "no" }
+        ))?;
+    }
+    let diffs = (0..old.mem.len())
+        .filter(|&addr| old.mem[addr] != new.mem[addr])
+        .collect::<Vec<_>>();
+    for &addr in diffs.iter().take(MAX_MEMORY_DIFF) {
+        term.write_line(&format!(
+            "mem[x{:04X}] ({}): x{:04X} -> x{:04X}",
+            addr,
+            symbol_table_query(symbol_table, addr),
+            old.mem[addr],
+            new.mem[addr]
+        ))?;
+    }
+    if diffs.len() > MAX_MEMORY_DIFF {
+        term.write_line(&format!(
+            "... 외 {}개의 메모리 값이 바뀌었습니다.",
+            diffs.len() - MAX_MEMORY_DIFF
+        ))?;
+    }
+    if !changed && diffs.is_empty() {
+        term.write_line("바뀐 값이 없습니다.")?;
+    }
+    Ok(())
+}
+
 /// Wrapper that re-implements `std::io::Read`
 /// To perform I/O directly with VM.
 pub struct TermWrapper<'a>(pub &'a mut Term);
@@ -107,21 +196,48 @@ impl<'a> Iterator for TermWrapper<'a> {
 }
 
 pub fn parse_usize_with_prefix(s: &str) -> Result<usize, std::num::ParseIntError> {
-    if s.len() >= 1 && &s[0..1] == "x" {
-        usize::from_str_radix(&s[1..], 16)
+    if let Some(hex) = s.strip_prefix('x') {
+        usize::from_str_radix(hex, 16)
     } else {
         s.parse::<usize>()
     }
 }
 
+/// 주소를 파싱합니다. x1234/1234 형태의 숫자, PC(pc), 또는 심볼 이름을 받습니다.
+pub fn parse_address(s: &str, vm: &VM, symbol_table: &[TableEntry]) -> Option<usize> {
+    if s.eq_ignore_ascii_case("pc") {
+        return Some(vm.pc as usize);
+    }
+    match parse_usize_with_prefix(s) {
+        Ok(addr) if addr < 1 << 16 => Some(addr),
+        Ok(_) => None,
+        Err(_) => symbol_table_lookup(symbol_table, s),
+    }
+}
+
 pub fn view_mem_entry(
     addr: usize,
     vm: &VM,
     symbol_table: &[TableEntry],
+    source_map: &SourceMap,
     term: &Term,
 ) -> IOResult<()> {
-    term.write_line(&format!(
-        "x{:04X} {:016b} x{:04X}   {:5} {:6}   {:<20} {}",
+    term.write_line(&format_mem_entry(addr, vm, symbol_table, source_map))
+}
+
+/// `view_mem_entry`가 출력하는 한 줄을 만듭니다.
+pub fn format_mem_entry(
+    addr: usize,
+    vm: &VM,
+    symbol_table: &[TableEntry],
+    source_map: &SourceMap,
+) -> String {
+    let source = match (source_map.location(addr), source_map.line_text(addr)) {
+        (Some(location), Some(text)) => format!("  ; {} {}", location, text.trim()),
+        _ => String::new(),
+    };
+    format!(
+        "x{:04X} {:016b} x{:04X}   {:5} {:6}   {:<20} {}{}",
         addr,
         vm.mem[addr],
         vm.mem[addr],
@@ -129,9 +245,10 @@ pub fn view_mem_entry(
         vm.mem[addr] as i16,
         format!("{}", Instruction::from_u16(vm.mem[addr])),
         decorate_instruction_str(
-            &symbol_table,
+            symbol_table,
             &format!("{}", Instruction::from_u16(vm.mem[addr])),
             addr
         ),
-    ))
+        source,
+    )
 }
@@ -0,0 +1,149 @@
+use crate::helper::{report_error, Status, TermWrapper};
+use console::Term;
+use std::io::Result as IOResult;
+
+/// "로 둘러싸인 문자열의 escape(\n, \t, \r, \0, \\, \", \xHH)를 풀어 바이트열로 만듭니다.
+pub fn parse_escaped(s: &str) -> Result<Vec<u8>, String> {
+    let inner = match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return Err(format!("\"로 둘러싸인 문자열이 아닙니다: {}", s)),
+    };
+    let mut bytes = vec![];
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next() {
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('r') => b'\r',
+            Some('0') => 0,
+            Some('\\') => b'\\',
+            Some('"') => b'"',
+            Some('x') => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(b) if hex.len() == 2 => b,
+                    _ => return Err(format!("잘못된 escape입니다: \\x{}", hex)),
+                }
+            }
+            Some(c) => return Err(format!("잘못된 escape입니다: \\{}", c)),
+            None => return Err("문자열이 \\로 끝납니다.".to_owned()),
+        });
+    }
+    Ok(bytes)
+}
+
+/// input 명령어로 설정할 입력의 출처
+#[derive(Debug, PartialEq)]
+enum Source<'a> {
+    /// Ctrl+W를 누를 때까지 터미널에서 읽습니다.
+    Terminal,
+    File(&'a str),
+    Bytes(Vec<u8>),
+}
+
+/// "[append] [file <path>|"escaped"|<text>]" 형태의 입력을 append 여부와 입력의 출처로 나눕니다.
+fn parse_source(body: &str) -> Result<(bool, Source<'_>), String> {
+    let body = body.trim();
+    let (append, body) = match body.strip_prefix("append") {
+        Some(rest) if rest.is_empty() || rest.starts_with(' ') => (true, rest.trim()),
+        _ => (false, body),
+    };
+
+    let source = if body.is_empty() {
+        Source::Terminal
+    } else if body == "file" {
+        return Err("사용법: input file <path>".to_owned());
+    } else if let Some(path) = body.strip_prefix("file ") {
+        Source::File(path.trim())
+    } else if body.starts_with('"') {
+        match parse_escaped(body) {
+            Ok(bytes) => Source::Bytes(bytes),
+            Err(err) => return Err(format!("잘못된 입력입니다: {}", err)),
+        }
+    } else {
+        Source::Bytes(body.as_bytes().to_vec())
+    };
+    Ok((append, source))
+}
+
+/// input [append] [file <path>|"escaped"|<text>]: 프로그램에 전달할 입력을 미리 설정합니다.
+/// append가 주어지면 기존 입력을 지우지 않고 뒤에 덧붙입니다.
+/// 내용이 주어지지 않으면 Ctrl+W를 누를 때까지 터미널에서 읽습니다. (interactive가 아닐 때는 실패합니다.)
+pub fn input_command(
+    pre_input: &mut Vec<u8>,
+    term: &mut Term,
+    body: Option<&str>,
+    interactive: bool,
+) -> IOResult<Status> {
+    let (append, source) = match parse_source(body.unwrap_or("")) {
+        Ok(parsed) => parsed,
+        Err(err) => return report_error(term, &err),
+    };
+
+    let bytes = match source {
+        Source::Terminal => {
+            if !interactive {
+                return report_error(
+                    term,
+                    "스크립트 모드에서는 input file <path> 또는 input \"내용\" 형태로 입력을 설정하세요.",
+                );
+            }
+            term.write_line("미리 입력될 내용을 모두 쓴 후, Ctrl+W를 누르세요.")?;
+            TermWrapper(term).collect::<Result<Vec<u8>, _>>()?
+        }
+        Source::File(path) => match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                return report_error(term, &format!("파일을 읽을 수 없습니다: {}: {}", path, err))
+            }
+        },
+        Source::Bytes(bytes) => bytes,
+    };
+
+    if append {
+        pre_input.extend_from_slice(&bytes);
+        term.write_line(&format!(
+            "입력을 {}바이트 덧붙였습니다. (총 {}바이트)",
+            bytes.len(),
+            pre_input.len()
+        ))?;
+    } else {
+        *pre_input = bytes;
+        term.write_line(&format!("입력을 설정했습니다: {}바이트", pre_input.len()))?;
+    }
+    Ok(Status::Done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_needs_a_path() {
+        for body in &["file", " file ", "append file"] {
+            assert!(parse_source(body).is_err(), "{:?}", body);
+        }
+        assert_eq!(
+            parse_source("file  in.txt "),
+            Ok((false, Source::File("in.txt")))
+        );
+    }
+
+    #[test]
+    fn append_is_a_separate_word() {
+        assert_eq!(parse_source("append"), Ok((true, Source::Terminal)));
+        assert_eq!(
+            parse_source("append \"a\\n\""),
+            Ok((true, Source::Bytes(b"a\n".to_vec())))
+        );
+        assert_eq!(
+            parse_source("appendix"),
+            Ok((false, Source::Bytes(b"appendix".to_vec())))
+        );
+    }
+}
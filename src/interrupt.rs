@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 마지막으로 확인한 이후 Ctrl+C(SIGINT)가 눌렸는지
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn handle_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Ctrl+C가 lc3dbg를 종료하지 않고, 실행 중인 프로그램만 멈추도록 SIGINT handler를 설치합니다.
+#[cfg(unix)]
+pub fn install_handler() {
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handle_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// unix가 아닌 환경에서는 Ctrl+C의 기본 동작(종료)을 그대로 둡니다.
+#[cfg(not(unix))]
+pub fn install_handler() {}
+
+/// 마지막으로 호출된 이후 Ctrl+C가 눌렸는지 반환하고, 기록을 지웁니다.
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
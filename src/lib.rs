@@ -1,4 +1,25 @@
+pub mod access;
+pub mod asm;
+pub mod breakpoint;
+pub mod callstack;
+pub mod checkpoint;
+pub mod coverage;
+pub mod expr;
+pub mod fault;
+pub mod harness;
 pub mod helper;
+pub mod input;
+pub mod interrupt;
+pub mod lineedit;
+pub mod loopcheck;
+pub mod output;
+pub mod profile;
+pub mod reverse;
 pub mod run;
+pub mod set;
+pub mod snapshot;
+pub mod source;
 pub mod symbol;
+pub mod trace;
 pub mod undo;
+pub mod uninit;
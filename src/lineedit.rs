@@ -0,0 +1,431 @@
+use crate::symbol::TableEntry;
+use console::Term;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{Result as IOResult, Write};
+use std::path::PathBuf;
+
+/// 기억해 둘 최대 명령어 기록의 수
+const MAX_HISTORY: usize = 1000;
+/// 명령어 기록을 저장할 파일의 이름. 홈 디렉토리에 만듭니다.
+const HISTORY_FILE: &str = ".lc3dbg_history";
+/// 명령어 이름 다음의 인자에서 자동 완성할 레지스터 이름
+const REGISTERS: &[&str] = &[
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "pc", "psr", "cc",
+];
+/// 인자에서 단어를 나누는 문자. (mem[LABEL], R1+LABEL 등)
+const SEPARATORS: &[char] = &[
+    '[', ']', '(', ')', '+', '-', '*', ',', '=', '!', '<', '>', '&', '|',
+];
+
+/// 터미널에서 읽은 키 하나
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Tab,
+    /// Ctrl+K: 커서부터 줄 끝까지 지웁니다.
+    KillEnd,
+    /// Ctrl+U: 줄 처음부터 커서까지 지웁니다.
+    KillStart,
+    /// Ctrl+W: 커서 앞의 단어를 지웁니다.
+    KillWord,
+    /// Ctrl+C: 입력하던 줄을 버립니다.
+    Cancel,
+    /// Ctrl+D
+    Eof,
+    Unknown,
+}
+
+/// 화면에서 차지하는 칸 수. 한글 등 전각 문자는 두 칸입니다.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6 => 2,
+        _ => 1,
+    }
+}
+
+fn common_prefix<'a>(candidates: &[&'a str]) -> &'a str {
+    let first = candidates[0];
+    let mut len = first.len();
+    for candidate in candidates[1..].iter() {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((idx, c), _)| idx + c.len_utf8())
+            .min(len);
+    }
+    &first[..len]
+}
+
+#[cfg(unix)]
+mod raw {
+    use super::Key;
+    use std::io::{Error, Result as IOResult};
+
+    /// 터미널을 한 글자씩 읽고 echo하지 않는 상태로 바꾸고, drop될 때 되돌립니다.
+    /// 출력의 줄바꿈 처리(OPOST)는 그대로 둡니다.
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> IOResult<RawMode> {
+            unsafe {
+                let mut original: libc::termios = std::mem::zeroed();
+                if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                    return Err(Error::last_os_error());
+                }
+                let mut raw = original;
+                raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+                raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+                raw.c_cc[libc::VMIN] = 1;
+                raw.c_cc[libc::VTIME] = 0;
+                if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &raw) != 0 {
+                    return Err(Error::last_os_error());
+                }
+                Ok(RawMode { original })
+            }
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &self.original);
+            }
+        }
+    }
+
+    fn read_byte() -> IOResult<u8> {
+        let mut byte = 0u8;
+        loop {
+            let read = unsafe {
+                libc::read(
+                    libc::STDIN_FILENO,
+                    &mut byte as *mut u8 as *mut libc::c_void,
+                    1,
+                )
+            };
+            match read {
+                1 => return Ok(byte),
+                // 입력이 닫혔다면 Ctrl+D처럼 다룹니다.
+                0 => return Ok(0x04),
+                _ => {
+                    let err = Error::last_os_error();
+                    if err.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// ESC [ 또는 ESC O 뒤의 escape sequence를 읽습니다.
+    fn read_escape() -> IOResult<Key> {
+        match read_byte()? {
+            b'[' | b'O' => (),
+            _ => return Ok(Key::Unknown),
+        }
+        let mut params = vec![];
+        let last = loop {
+            let byte = read_byte()?;
+            if (0x40..=0x7E).contains(&byte) {
+                break byte;
+            }
+            params.push(byte);
+        };
+        Ok(match (last, params.as_slice()) {
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) | (b'~', b"1") | (b'~', b"7") => Key::Home,
+            (b'F', _) | (b'~', b"4") | (b'~', b"8") => Key::End,
+            (b'~', b"3") => Key::Delete,
+            _ => Key::Unknown,
+        })
+    }
+
+    pub fn read_key() -> IOResult<Key> {
+        let byte = read_byte()?;
+        Ok(match byte {
+            b'\r' | b'\n' => Key::Enter,
+            0x7F | 0x08 => Key::Backspace,
+            b'\t' => Key::Tab,
+            0x01 => Key::Home,
+            0x05 => Key::End,
+            0x02 => Key::Left,
+            0x06 => Key::Right,
+            0x10 => Key::Up,
+            0x0E => Key::Down,
+            0x0B => Key::KillEnd,
+            0x15 => Key::KillStart,
+            0x17 => Key::KillWord,
+            0x03 => Key::Cancel,
+            0x04 => Key::Eof,
+            0x1B => read_escape()?,
+            0x00..=0x1F => Key::Unknown,
+            0x80..=0xFF => {
+                // UTF-8 문자의 나머지 byte를 읽습니다.
+                let len = match byte {
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    0xF0..=0xF7 => 4,
+                    _ => return Ok(Key::Unknown),
+                };
+                let mut bytes = vec![byte];
+                for _ in 1..len {
+                    bytes.push(read_byte()?);
+                }
+                match std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                {
+                    Some(c) => Key::Char(c),
+                    None => Key::Unknown,
+                }
+            }
+            _ => Key::Char(byte as char),
+        })
+    }
+}
+
+/// 프롬프트에서 명령어를 읽는 line editor.
+/// 방향키로 커서를 옮기거나 이전 명령어를 불러오고, Tab으로 명령어, 심볼, 레지스터 이름을 완성합니다.
+pub struct LineEditor {
+    /// 자동 완성할 명령어 이름들
+    commands: &'static [&'static str],
+    history: Vec<String>,
+    /// 이번 세션에서 마지막으로 입력한 명령어
+    last: Option<String>,
+    /// 명령어 기록을 저장할 파일. 홈 디렉토리를 모르면 저장하지 않습니다.
+    path: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// 홈 디렉토리의 명령어 기록을 불러옵니다.
+    pub fn new(commands: &'static [&'static str]) -> LineEditor {
+        let path = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        let mut history = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| {
+                text.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+            // 파일이 계속 커지지 않도록 남길 기록만 다시 씁니다.
+            if let Some(path) = path.as_ref() {
+                let _ = fs::write(path, history.join("\n") + "\n");
+            }
+        }
+        LineEditor {
+            commands,
+            history,
+            last: None,
+            path,
+        }
+    }
+
+    /// 명령어를 기록에 추가하고 파일에 덧붙입니다. 바로 앞의 기록과 같다면 추가하지 않습니다.
+    pub fn add_history(&mut self, line: &str) {
+        self.last = Some(line.to_owned());
+        if self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(line.to_owned());
+        if let Some(path) = self.path.as_ref() {
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    /// 이번 세션에서 마지막으로 입력한 명령어
+    pub fn last_command(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// 커서 앞의 단어를 완성할 후보들과 그 단어의 시작 위치를 반환합니다.
+    fn candidates<'a>(
+        &self,
+        line: &[char],
+        cursor: usize,
+        symbol_table: &'a [TableEntry],
+    ) -> (usize, Vec<&'a str>) {
+        let before = &line[..cursor];
+        let first_word = !before.iter().any(|c| c.is_whitespace());
+        let start = before
+            .iter()
+            .rposition(|&c| c.is_whitespace() || (!first_word && SEPARATORS.contains(&c)))
+            .map_or(0, |idx| idx + 1);
+        let word = before[start..].iter().collect::<String>();
+        let previous = before[..start].iter().collect::<String>();
+        let mut candidates: Vec<&'a str> = if first_word || previous.trim() == "help" {
+            self.commands.to_vec()
+        } else {
+            let mut names = REGISTERS.to_vec();
+            names.extend(symbol_table.iter().filter_map(|entry| match entry {
+                TableEntry::Symbol(name) => Some(name.as_str()),
+                _ => None,
+            }));
+            names
+        };
+        candidates.retain(|candidate| candidate.starts_with(&word));
+        candidates.sort_unstable();
+        candidates.dedup();
+        (start, candidates)
+    }
+
+    /// 프롬프트와 입력 중인 줄을 다시 그리고, 커서를 제자리로 옮깁니다.
+    fn redraw(term: &Term, prompt: &str, line: &[char], cursor: usize) -> IOResult<()> {
+        let text = line.iter().collect::<String>();
+        let back = line[cursor..].iter().map(|&c| char_width(c)).sum::<usize>();
+        let mut out = format!("\r\x1b[2K{}{}", prompt, text);
+        if back > 0 {
+            out += &format!("\x1b[{}D", back);
+        }
+        term.write_str(&out)?;
+        term.flush()
+    }
+
+    /// 한 줄을 읽습니다. 빈 줄에서 Ctrl+D를 누르면 None을 반환합니다.
+    #[cfg(unix)]
+    pub fn read_line(
+        &mut self,
+        term: &Term,
+        prompt: &str,
+        symbol_table: &[TableEntry],
+    ) -> IOResult<Option<String>> {
+        let _raw = raw::RawMode::enable()?;
+        let mut line: Vec<char> = vec![];
+        let mut cursor = 0;
+        // 방향키로 불러온 기록의 위치. history.len()이면 새로 입력하는 줄입니다.
+        let mut position = self.history.len();
+        let mut editing: Vec<char> = vec![];
+        Self::redraw(term, prompt, &line, cursor)?;
+        loop {
+            match raw::read_key()? {
+                Key::Enter => {
+                    term.write_str("\n")?;
+                    return Ok(Some(line.into_iter().collect()));
+                }
+                Key::Eof if line.is_empty() => {
+                    term.write_str("\n")?;
+                    return Ok(None);
+                }
+                Key::Eof | Key::Delete => {
+                    if cursor < line.len() {
+                        line.remove(cursor);
+                    }
+                }
+                Key::Cancel => {
+                    term.write_str("^C\n")?;
+                    line.clear();
+                    cursor = 0;
+                    position = self.history.len();
+                }
+                Key::Char(c) => {
+                    line.insert(cursor, c);
+                    cursor += 1;
+                }
+                Key::Backspace if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+                Key::Left if cursor > 0 => cursor -= 1,
+                Key::Right if cursor < line.len() => cursor += 1,
+                Key::Home => cursor = 0,
+                Key::End => cursor = line.len(),
+                Key::KillEnd => line.truncate(cursor),
+                Key::KillStart => {
+                    line.drain(..cursor);
+                    cursor = 0;
+                }
+                Key::KillWord => {
+                    let mut start = cursor;
+                    while start > 0 && line[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    while start > 0 && !line[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    line.drain(start..cursor);
+                    cursor = start;
+                }
+                Key::Up if position > 0 => {
+                    if position == self.history.len() {
+                        editing = line.clone();
+                    }
+                    position -= 1;
+                    line = self.history[position].chars().collect();
+                    cursor = line.len();
+                }
+                Key::Down if position < self.history.len() => {
+                    position += 1;
+                    line = match self.history.get(position) {
+                        Some(entry) => entry.chars().collect(),
+                        None => editing.clone(),
+                    };
+                    cursor = line.len();
+                }
+                Key::Tab => {
+                    let (start, candidates) = self.candidates(&line, cursor, symbol_table);
+                    if candidates.is_empty() {
+                        continue;
+                    }
+                    let completion = if candidates.len() == 1 {
+                        format!("{} ", candidates[0])
+                    } else {
+                        common_prefix(&candidates).to_owned()
+                    };
+                    if completion.chars().count() > cursor - start {
+                        line.splice(start..cursor, completion.chars());
+                        cursor = start + completion.chars().count();
+                    } else {
+                        // 더 완성할 수 없다면 후보들을 보여 줍니다.
+                        term.write_str("\n")?;
+                        term.write_line(&candidates.join("  "))?;
+                    }
+                }
+                _ => continue,
+            }
+            Self::redraw(term, prompt, &line, cursor)?;
+        }
+    }
+
+    /// 터미널을 직접 다룰 수 없는 환경에서는 줄 단위로 읽습니다.
+    #[cfg(not(unix))]
+    pub fn read_line(
+        &mut self,
+        term: &Term,
+        prompt: &str,
+        _symbol_table: &[TableEntry],
+    ) -> IOResult<Option<String>> {
+        term.write_str(prompt)?;
+        term.read_line().map(Some)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::access::MemoryAccess;
+use crate::helper::{report_error, Status};
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::VM;
+use std::collections::HashMap;
+use std::io::Result as IOResult;
+
+/// 장치 레지스터 영역의 시작 주소. 여기에 접근하는 것을 입출력으로 봅니다.
+const DEVICE_SPACE: usize = 0xFE00;
+/// 입출력 없이 이만큼의 instruction을 실행하면 좁은 루프인지 확인합니다.
+const DEFAULT_LIMIT: usize = 1_000_000;
+/// 이 크기(word) 안에서만 실행했다면 좁은 루프로 봅니다.
+const TIGHT_LOOP_SPAN: u16 = 64;
+/// 같은 상태를 찾기 위해 기억해 둘 최대 상태 수. 넘으면 처음부터 다시 기억합니다.
+const MAX_STATES: usize = 1 << 16;
+
+/// 무한 루프로 판단한 이유
+pub enum InfiniteLoop {
+    /// 메모리 쓰기와 입출력 없이 같은 상태(PC, 레지스터, CC)로 돌아왔습니다.
+    Repeated { start: u16, end: u16, period: usize },
+    /// 입출력 없이 좁은 범위 안에서 limit개의 instruction을 실행했습니다.
+    Tight { start: u16, end: u16, limit: usize },
+}
+
+impl InfiniteLoop {
+    pub fn describe(&self, symbol_table: &[TableEntry]) -> String {
+        let range = |start: u16, end: u16| {
+            format!(
+                "x{:04X} ({}) - x{:04X} ({})",
+                start,
+                symbol_table_query(symbol_table, start as usize),
+                end,
+                symbol_table_query(symbol_table, end as usize)
+            )
+        };
+        match *self {
+            InfiniteLoop::Repeated { start, end, period } => format!(
+                "무한 루프입니다: {}. 메모리 쓰기와 입출력 없이 {}개의 instruction마다 같은 상태(PC, 레지스터, CC)가 반복됩니다.",
+                range(start, end),
+                period
+            ),
+            InfiniteLoop::Tight { start, end, limit } => format!(
+                "무한 루프일 수 있습니다: {}. 입출력 없이 이 범위에서만 {}개의 instruction을 실행했습니다. (loopcheck <n>으로 한도를 바꿀 수 있습니다.)",
+                range(start, end),
+                limit
+            ),
+        }
+    }
+}
+
+/// instruction을 실행하기 전의 상태 중 무한 루프를 판단하는 데 쓰는 부분
+#[derive(PartialEq, Eq, Hash)]
+struct State {
+    pc: u16,
+    register: [i16; 8],
+    cc: (bool, bool, bool),
+}
+
+/// run 등으로 실행하는 동안 무한 루프를 찾습니다.
+pub struct LoopDetector {
+    pub enabled: bool,
+    /// 입출력 없이 좁은 범위에서 이만큼 실행하면 멈춥니다.
+    pub limit: usize,
+    /// 마지막 메모리 쓰기나 입출력 이후의 상태와, 그 상태가 pcs의 몇 번째인지
+    states: HashMap<State, usize>,
+    /// 마지막 메모리 쓰기나 입출력 이후에 실행한 PC들
+    pcs: Vec<u16>,
+    /// 마지막 입출력 이후에 실행한 instruction의 수와 PC의 범위
+    window: usize,
+    window_min: u16,
+    window_max: u16,
+}
+
+impl Default for LoopDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoopDetector {
+    pub fn new() -> LoopDetector {
+        LoopDetector {
+            enabled: true,
+            limit: DEFAULT_LIMIT,
+            states: HashMap::new(),
+            pcs: vec![],
+            window: 0,
+            window_min: u16::MAX,
+            window_max: 0,
+        }
+    }
+
+    /// 실행을 시작할 때 호출해, 이전 실행에서 기억한 상태를 지웁니다.
+    pub fn start(&mut self) {
+        self.forget_states();
+        self.reset_window();
+    }
+
+    fn forget_states(&mut self) {
+        self.states.clear();
+        self.pcs.clear();
+    }
+
+    fn reset_window(&mut self) {
+        self.window = 0;
+        self.window_min = u16::MAX;
+        self.window_max = 0;
+    }
+
+    /// PC의 instruction을 실행하기 전에 호출합니다. access는 그 instruction이 접근할 메모리입니다.
+    pub fn check(&mut self, vm: &VM, access: &MemoryAccess) -> Option<InfiniteLoop> {
+        if !self.enabled {
+            return None;
+        }
+        let state = State {
+            pc: vm.pc,
+            register: vm.register,
+            cc: (vm.condition.n, vm.condition.z, vm.condition.p),
+        };
+        if let Some(&idx) = self.states.get(&state) {
+            let pcs = &self.pcs[idx..];
+            return Some(InfiniteLoop::Repeated {
+                start: *pcs.iter().min().unwrap(),
+                end: *pcs.iter().max().unwrap(),
+                period: pcs.len(),
+            });
+        }
+
+        let io = access
+            .reads
+            .iter()
+            .chain(access.write.iter())
+            .any(|&addr| addr >= DEVICE_SPACE);
+        if io {
+            self.forget_states();
+            self.reset_window();
+            return None;
+        }
+        if access.write.is_some() || self.pcs.len() >= MAX_STATES {
+            self.forget_states();
+        } else {
+            self.states.insert(state, self.pcs.len());
+            self.pcs.push(vm.pc);
+        }
+
+        self.window += 1;
+        self.window_min = self.window_min.min(vm.pc);
+        self.window_max = self.window_max.max(vm.pc);
+        if self.window >= self.limit {
+            if self.window_max - self.window_min < TIGHT_LOOP_SPAN {
+                return Some(InfiniteLoop::Tight {
+                    start: self.window_min,
+                    end: self.window_max,
+                    limit: self.limit,
+                });
+            }
+            self.reset_window();
+        }
+        None
+    }
+
+    /// loopcheck [on|off|<n>]: 무한 루프 검사를 켜거나 끄고, 좁은 루프로 판단할 instruction 수를 정합니다.
+    pub fn loopcheck_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        match body.map(str::trim) {
+            None | Some("") => (),
+            Some("on") => self.enabled = true,
+            Some("off") => self.enabled = false,
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => {
+                    self.enabled = true;
+                    self.limit = n;
+                }
+                _ => return report_error(term, "사용법: loopcheck [on|off|<n>]"),
+            },
+        }
+        if self.enabled {
+            term.write_line(&format!(
+                "무한 루프 검사가 켜져 있습니다. (같은 상태의 반복, 입출력 없이 좁은 범위에서 {}개의 instruction 실행)",
+                self.limit
+            ))?;
+        } else {
+            term.write_line("무한 루프 검사가 꺼져 있습니다.")?;
+        }
+        Ok(Status::Done)
+    }
+}
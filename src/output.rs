@@ -0,0 +1,123 @@
+use crate::helper::{report_error, Status};
+use console::Term;
+use std::borrow::Cow;
+use std::io::{Result as IOResult, Write};
+
+/// 프로그램이 콘솔에 출력한 내용을 모아 두는 버퍼
+pub struct OutputBuffer {
+    data: Vec<u8>,
+    /// true라면 출력을 버퍼에 모으는 동시에 터미널에도 출력합니다.
+    pub mirror: bool,
+}
+
+impl OutputBuffer {
+    pub fn new(mirror: bool) -> OutputBuffer {
+        OutputBuffer {
+            data: vec![],
+            mirror,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.data)
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// 이 버퍼에 출력을 모으는 writer를 만듭니다.
+    pub fn writer<'a>(&'a mut self, term: &Term) -> CaptureWriter<'a> {
+        CaptureWriter {
+            buffer: self,
+            term: term.clone(),
+            line_open: false,
+        }
+    }
+
+    /// output [clear|save <path>|mirror on|off]: 모인 출력을 보거나, 지우거나, 파일로 저장합니다.
+    pub fn output_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        let body = body.unwrap_or("").trim();
+        let (sub, arg) = match body.find(' ') {
+            Some(idx) => (&body[..idx], body[idx + 1..].trim()),
+            None => (body, ""),
+        };
+        match (sub, arg) {
+            ("", _) => {
+                term.write_line(&format!(
+                    "--- 프로그램 출력 ({}바이트) ---",
+                    self.data.len()
+                ))?;
+                term.write_str(&self.text())?;
+                if self.data.last().is_some_and(|&b| b != b'\n') {
+                    term.write_line("")?;
+                }
+                term.write_line("--- 끝 ---")?;
+            }
+            ("clear", "") => {
+                self.clear();
+                term.write_line("모인 출력을 지웠습니다.")?;
+            }
+            ("save", "") => return report_error(term, "저장할 파일 이름을 입력하세요."),
+            ("save", path) => match std::fs::write(path, &self.data) {
+                Ok(()) => term.write_line(&format!(
+                    "출력을 저장했습니다: {} ({}바이트)",
+                    path,
+                    self.data.len()
+                ))?,
+                Err(err) => {
+                    return report_error(term, &format!("저장하지 못했습니다: {}: {}", path, err))
+                }
+            },
+            ("mirror", "on") | ("mirror", "off") => {
+                self.mirror = arg == "on";
+                term.write_line(if self.mirror {
+                    "프로그램 출력을 터미널에도 출력합니다."
+                } else {
+                    "프로그램 출력을 버퍼에만 모읍니다."
+                })?;
+            }
+            _ => return report_error(term, "사용법: output [clear|save <path>|mirror on|off]"),
+        }
+        Ok(Status::Done)
+    }
+}
+
+/// VM의 출력을 OutputBuffer에 모으고, mirror가 켜져 있다면 터미널에도 출력하는 writer
+pub struct CaptureWriter<'a> {
+    buffer: &'a mut OutputBuffer,
+    term: Term,
+    /// 터미널에 출력한 마지막 줄이 줄바꿈으로 끝나지 않았는지 여부
+    line_open: bool,
+}
+
+impl<'a> CaptureWriter<'a> {
+    /// 터미널에 출력한 프로그램 출력이 줄바꿈으로 끝나지 않았다면 줄을 바꿔,
+    /// 뒤따르는 디버거의 메시지가 프로그램 출력과 섞이지 않게 합니다.
+    pub fn finish_line(&mut self) -> IOResult<()> {
+        if self.line_open {
+            self.line_open = false;
+            self.term.write_line("")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Write for CaptureWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        self.buffer.data.extend_from_slice(buf);
+        if self.buffer.mirror && !buf.is_empty() {
+            self.term.write_all(buf)?;
+            self.line_open = buf.last() != Some(&b'\n');
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+        self.term.flush()
+    }
+}
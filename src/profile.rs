@@ -0,0 +1,214 @@
+use crate::callstack::Frame;
+use crate::helper::{report_error, Status};
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::collections::HashMap;
+use std::io::Result as IOResult;
+
+/// report에서 항목별로 보여줄 기본 개수
+const DEFAULT_REPORT_SIZE: usize = 10;
+
+/// 서브루틴 하나의 실행 통계
+#[derive(Default, Clone, Copy)]
+struct RoutineStats {
+    calls: u64,
+    /// 서브루틴 안에서(더 안쪽 호출 포함) 실행한 instruction의 수
+    inclusive: u64,
+    /// 서브루틴 자신의 instruction만 센 수
+    exclusive: u64,
+}
+
+/// 주소마다 실행 횟수를 세고, 서브루틴별로 모읍니다.
+/// 키가 None인 서브루틴은 어떤 서브루틴에도 속하지 않은 최상위 코드입니다.
+pub struct Profiler {
+    pub enabled: bool,
+    counts: Vec<u64>,
+    total: u64,
+    /// (서브루틴의 시작 주소, 호출되었을 때의 total)
+    stack: Vec<(u16, u64)>,
+    routines: HashMap<Option<u16>, RoutineStats>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            enabled: false,
+            counts: vec![0; 65536],
+            total: 0,
+            stack: vec![],
+            routines: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Profiler {
+            enabled: self.enabled,
+            ..Profiler::new()
+        };
+    }
+
+    /// pc의 instruction을 실행한 직후에 호출합니다. frames는 실행 후의 호출 스택입니다.
+    pub fn record(&mut self, pc: u16, frames: &[Frame]) {
+        self.counts[pc as usize] += 1;
+        self.total += 1;
+        let current = self.stack.last().map(|&(target, _)| target);
+        self.routines.entry(current).or_default().exclusive += 1;
+
+        // 호출 스택과 깊이를 맞춥니다. 빠져나온 서브루틴은 inclusive를 확정합니다.
+        while self.stack.len() > frames.len() {
+            let (target, start) = self.stack.pop().unwrap();
+            // 재귀 호출은 가장 바깥쪽 호출에서만 셉니다.
+            if self.stack.iter().all(|&(t, _)| t != target) {
+                self.routines.entry(Some(target)).or_default().inclusive += self.total - start;
+            }
+        }
+        for frame in frames[self.stack.len()..].iter() {
+            self.routines.entry(Some(frame.target)).or_default().calls += 1;
+            self.stack.push((frame.target, self.total));
+        }
+    }
+
+    /// 아직 돌아오지 않은 호출까지 포함한 서브루틴별 통계
+    fn routine_stats(&self) -> Vec<(Option<u16>, RoutineStats)> {
+        let mut routines = self.routines.clone();
+        for (idx, &(target, start)) in self.stack.iter().enumerate() {
+            if self.stack[..idx].iter().all(|&(t, _)| t != target) {
+                routines.entry(Some(target)).or_default().inclusive += self.total - start;
+            }
+        }
+        routines.entry(None).or_default().inclusive = self.total;
+        routines.into_iter().collect()
+    }
+
+    fn percent(&self, n: u64) -> f64 {
+        n as f64 * 100.0 / self.total as f64
+    }
+
+    fn report(&self, vm: &VM, symbol_table: &[TableEntry], term: &Term, n: usize) -> IOResult<()> {
+        if self.total == 0 {
+            return term.write_line("기록된 instruction이 없습니다.");
+        }
+        term.write_line(&format!(
+            "총 {}개의 instruction을 실행했습니다.",
+            self.total
+        ))?;
+
+        let mut addrs = (0..self.counts.len())
+            .filter(|&addr| self.counts[addr] > 0)
+            .collect::<Vec<_>>();
+        addrs.sort_by_key(|&addr| std::cmp::Reverse(self.counts[addr]));
+        term.write_line("")?;
+        term.write_line("가장 많이 실행된 instruction:")?;
+        term.write_line("      count       %  addr   symbol           instruction")?;
+        for &addr in addrs.iter().take(n) {
+            term.write_line(&format!(
+                "{:>11} {:>6.2}%  x{:04X}  {:<16} {}",
+                self.counts[addr],
+                self.percent(self.counts[addr]),
+                addr,
+                symbol_table_query(symbol_table, addr),
+                Instruction::from_u16(vm.mem[addr])
+            ))?;
+        }
+
+        let mut symbols: HashMap<&str, u64> = HashMap::new();
+        for &addr in addrs.iter() {
+            let name = match &symbol_table[addr] {
+                TableEntry::Symbol(name) => name.as_str(),
+                TableEntry::Relative(offset) => symbol_table[addr - offset].unwrap_symbol_name(),
+                TableEntry::Unknown => "?",
+            };
+            *symbols.entry(name).or_default() += self.counts[addr];
+        }
+        let mut symbols = symbols.into_iter().collect::<Vec<_>>();
+        symbols.sort_by_key(|&(name, count)| (std::cmp::Reverse(count), name));
+        term.write_line("")?;
+        term.write_line("가장 많이 실행된 심볼(레이블부터 다음 레이블 전까지):")?;
+        term.write_line("      count       %  symbol")?;
+        for (name, count) in symbols.iter().take(n) {
+            term.write_line(&format!(
+                "{:>11} {:>6.2}%  {}",
+                count,
+                self.percent(*count),
+                name
+            ))?;
+        }
+
+        let mut routines = self.routine_stats();
+        routines.sort_by_key(|&(target, stats)| (std::cmp::Reverse(stats.inclusive), target));
+        term.write_line("")?;
+        term.write_line("서브루틴별 실행 횟수(inclusive는 더 안쪽 호출을 포함합니다):")?;
+        term.write_line("      calls   inclusive       %   exclusive       %  subroutine")?;
+        for (target, stats) in routines.iter().take(n) {
+            let name = match target {
+                Some(addr) => format!(
+                    "x{:04X} ({})",
+                    addr,
+                    symbol_table_query(symbol_table, *addr as usize)
+                ),
+                None => "(최상위)".to_owned(),
+            };
+            term.write_line(&format!(
+                "{:>11} {:>11} {:>6.2}% {:>11} {:>6.2}%  {}",
+                stats.calls,
+                stats.inclusive,
+                self.percent(stats.inclusive),
+                stats.exclusive,
+                self.percent(stats.exclusive),
+                name
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// profile on|off|report [n]|clear: 실행 횟수를 세기 시작하거나 멈추고, 결과를 출력합니다.
+    pub fn profile_command(
+        &mut self,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+        term: &Term,
+        body: Option<&str>,
+    ) -> IOResult<Status> {
+        let args = body.unwrap_or("").split_whitespace().collect::<Vec<_>>();
+        match args.as_slice() {
+            [] => term.write_line(&format!(
+                "프로파일러가 {}. ({}개의 instruction을 기록했습니다.)",
+                if self.enabled {
+                    "켜져 있습니다"
+                } else {
+                    "꺼져 있습니다"
+                },
+                self.total
+            ))?,
+            ["on"] => {
+                self.enabled = true;
+                term.write_line("실행하는 instruction의 수를 셉니다.")?;
+            }
+            ["off"] => {
+                self.enabled = false;
+                term.write_line(
+                    "instruction의 수를 세지 않습니다. 기록은 profile report로 볼 수 있습니다.",
+                )?;
+            }
+            ["clear"] => {
+                self.clear();
+                term.write_line("기록을 지웠습니다.")?;
+            }
+            ["report"] => self.report(vm, symbol_table, term, DEFAULT_REPORT_SIZE)?,
+            ["report", n] => match n.parse::<usize>() {
+                Ok(n) => self.report(vm, symbol_table, term, n)?,
+                Err(err) => return report_error(term, &format!("잘못된 입력입니다: {}", err)),
+            },
+            _ => return report_error(term, "사용법: profile [on|off|report [n]|clear]"),
+        }
+        Ok(Status::Done)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::access::memory_access;
+use crate::breakpoint::BreakpointSet;
+use crate::helper::print_state_diff;
+use crate::run::call_depth_delta;
+use crate::symbol::{symbol_table_query, TableEntry};
+use crate::undo::UndoBuffer;
+use console::Term;
+use lc3::vm::VM;
+use std::io::Result as IOResult;
+
+/// 거꾸로 실행할 때 언제 멈출지 정합니다.
+#[derive(Clone, Copy)]
+pub enum ReverseUntil {
+    /// instruction 하나를 되돌립니다.
+    Step,
+    /// 현재 서브루틴을 호출한 JSR/JSRR/TRAP을 실행하기 직전까지 되돌립니다.
+    Finish,
+    /// breakpoint/watchpoint에 마지막으로 걸렸던 곳까지 되돌립니다.
+    Continue,
+}
+
+/// vm을 한 instruction씩 되돌리며 until 조건을 만족하는 곳에서 멈춥니다.
+/// 되돌린 instruction의 개수와 멈춘 이유를 반환합니다.
+/// at_watch_stop은 지금 watchpoint에 걸려 멈춰 있는지 여부로, 그렇다면 처음 되돌린 instruction의 접근은 건너뜁니다.
+/// 돌아갈 때는 watchpoint에 걸려 멈췄는지 여부로 바뀝니다.
+fn rewind(
+    vm: &mut VM,
+    until: ReverseUntil,
+    at_watch_stop: &mut bool,
+    buffer: &mut UndoBuffer,
+    breakpoints: &BreakpointSet,
+    symbol_table: &[TableEntry],
+) -> (usize, Option<String>) {
+    let skip_first_watch = *at_watch_stop;
+    *at_watch_stop = false;
+    let mut n = 0;
+    let mut depth = 0isize;
+    while buffer.undo_one(vm) {
+        // 이제 vm은 되돌린 instruction을 실행하기 직전의 상태입니다.
+        let pc = vm.pc as usize;
+        match until {
+            ReverseUntil::Step => return (1, None),
+            ReverseUntil::Finish => {
+                depth -= call_depth_delta(vm.mem[pc]);
+                if depth < 0 {
+                    return (n + 1, None);
+                }
+            }
+            ReverseUntil::Continue => {
+                // watchpoint는 접근한 instruction을 실행한 직후의 상태에서 조건을 검사하고 멈춥니다.
+                let hits = breakpoints.watch_hits(vm, &memory_access(vm));
+                if !hits.is_empty() && (n > 0 || !skip_first_watch) {
+                    let hit = buffer
+                        .state_after_undone(vm)
+                        .and_then(|after| breakpoints.reverse_watch_hit(hits, &after));
+                    if let Some(hit) = hit {
+                        buffer.redo_one(vm);
+                        *at_watch_stop = true;
+                        return (
+                            n,
+                            Some(format!(
+                                "{}번 watchpoint: x{:04X} ({}) {}, PC: x{:04X} ({})",
+                                hit.id,
+                                hit.addr,
+                                symbol_table_query(symbol_table, hit.addr),
+                                if hit.write { "쓰기" } else { "읽기" },
+                                pc,
+                                symbol_table_query(symbol_table, pc)
+                            )),
+                        );
+                    }
+                }
+                if let Some(id) = breakpoints.reverse_hit(vm) {
+                    return (
+                        n + 1,
+                        Some(format!(
+                            "{}번 breakpoint: x{:04X} ({})",
+                            id,
+                            pc,
+                            symbol_table_query(symbol_table, pc)
+                        )),
+                    );
+                }
+            }
+        }
+        n += 1;
+    }
+    (n, Some("기록의 처음까지 되돌렸습니다.".to_owned()))
+}
+
+/// reverse-step/reverse-finish/reverse-continue: undo buffer의 기록을 이용해 거꾸로 실행하고,
+/// 되돌리기 전과 비교해 바뀐 값을 출력합니다. 되돌린 instruction의 개수를 반환하고,
+/// 되돌릴 기록이 없다면 None을 반환합니다.
+/// breakpoint의 hit/ignore 횟수는 바뀌지 않습니다.
+/// at_watch_stop은 직전의 실행이 watchpoint에 걸려 멈췄고 그 뒤로 VM이 바뀌지 않았는지 여부이며,
+/// 거꾸로 실행한 뒤에는 watchpoint에 걸려 멈췄는지 여부로 바뀝니다.
+pub fn reverse_command(
+    vm: &mut VM,
+    term: &Term,
+    until: ReverseUntil,
+    at_watch_stop: &mut bool,
+    undo_buffer: Option<&mut UndoBuffer>,
+    breakpoints: &BreakpointSet,
+    symbol_table: &[TableEntry],
+) -> IOResult<Option<usize>> {
+    let buffer = match undo_buffer {
+        Some(buffer) => buffer,
+        None => {
+            term.write_line("undo buffer가 설정되지 않았습니다.")?;
+            term.write_line("먼저 buffer <size> 명령어로 버퍼를 초기화하세요.")?;
+            return Ok(None);
+        }
+    };
+    if buffer.is_empty() {
+        term.write_line("되돌릴 기록이 없습니다.")?;
+        return Ok(None);
+    }
+
+    let old = vm.clone();
+    let (n, reason) = rewind(vm, until, at_watch_stop, buffer, breakpoints, symbol_table);
+    term.write_line(&format!("{}개의 instruction을 되돌렸습니다.", n))?;
+    if let Some(reason) = reason {
+        term.write_line(&reason)?;
+    }
+    print_state_diff(&old, vm, symbol_table, term)?;
+    Ok(Some(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::breakpoint::{BreakpointKind, WatchAccess};
+    use std::io::{empty, sink};
+
+    /// X에 쓰는 ST를 두 번 지나가는 프로그램을 n개의 instruction만큼 실행하며 기록합니다.
+    fn record(n: usize) -> (VM, UndoBuffer) {
+        let assembly = match assemble(
+            ".ORIG x3000
+        ADD R0, R0, #1
+        ST R0, X
+        ADD R0, R0, #1
+        ST R0, X
+        ADD R0, R0, #1
+        HALT
+X       .FILL 0
+        .END",
+        ) {
+            Ok(assembly) => assembly,
+            Err(_) => panic!("어셈블하지 못했습니다."),
+        };
+        let mut vm = VM::new();
+        assembly.load(&mut vm);
+        let mut buffer = UndoBuffer::new(100);
+        for _ in 0..n {
+            buffer.push(&vm);
+            vm.step(&mut empty(), &mut sink());
+        }
+        (vm, buffer)
+    }
+
+    fn watch_x() -> BreakpointSet {
+        let mut breakpoints = BreakpointSet::new();
+        let kind = BreakpointKind::Watch {
+            len: 1,
+            access: WatchAccess::Write,
+        };
+        breakpoints.insert(0x3006, kind, None);
+        breakpoints
+    }
+
+    fn reverse_continue(vm: &mut VM, buffer: &mut UndoBuffer, at_watch_stop: &mut bool) -> usize {
+        let symbol_table = vec![TableEntry::Unknown; 65536];
+        let (n, _) = rewind(
+            vm,
+            ReverseUntil::Continue,
+            at_watch_stop,
+            buffer,
+            &watch_x(),
+            &symbol_table,
+        );
+        n
+    }
+
+    #[test]
+    fn reverse_continue_stops_after_the_watched_write() {
+        let (mut vm, mut buffer) = record(5);
+        let mut at_watch_stop = false;
+        assert_eq!(
+            reverse_continue(&mut vm, &mut buffer, &mut at_watch_stop),
+            1
+        );
+        assert_eq!((vm.pc, vm.register[0], vm.mem[0x3006]), (0x3004, 2, 2));
+        assert!(at_watch_stop);
+
+        // 방금 멈춘 watchpoint는 건너뛰고 그 앞의 쓰기까지 되돌립니다.
+        assert_eq!(
+            reverse_continue(&mut vm, &mut buffer, &mut at_watch_stop),
+            2
+        );
+        assert_eq!((vm.pc, vm.register[0], vm.mem[0x3006]), (0x3002, 1, 1));
+        assert!(at_watch_stop);
+    }
+
+    #[test]
+    fn reverse_continue_does_not_skip_a_hit_it_did_not_stop_at() {
+        // 방금 실행한 ST가 watchpoint에 걸렸지만, 그 자리에서 멈춘 것이 아니라면 건너뛰지 않습니다.
+        let (mut vm, mut buffer) = record(4);
+        let mut at_watch_stop = false;
+        assert_eq!(
+            reverse_continue(&mut vm, &mut buffer, &mut at_watch_stop),
+            0
+        );
+        assert_eq!((vm.pc, vm.register[0], vm.mem[0x3006]), (0x3004, 2, 2));
+
+        assert_eq!(
+            reverse_continue(&mut vm, &mut buffer, &mut at_watch_stop),
+            2
+        );
+        assert_eq!((vm.pc, vm.register[0], vm.mem[0x3006]), (0x3002, 1, 1));
+    }
+}
@@ -1,69 +1,366 @@
-use crate::helper::TermWrapper;
+use crate::access::memory_access;
+use crate::breakpoint::BreakpointSet;
+use crate::callstack::CallStack;
+use crate::coverage::Coverage;
+use crate::fault::{Fault, FaultDetector};
+use crate::helper::{report_error, Status, TermWrapper};
+use crate::interrupt::take_interrupt;
+use crate::loopcheck::LoopDetector;
+use crate::output::OutputBuffer;
+use crate::profile::Profiler;
+use crate::symbol::{symbol_table_query, TableEntry};
+use crate::trace::{TraceEntry, Tracer};
 use crate::undo::UndoBuffer;
+use crate::uninit::{UninitMode, UninitTracker};
 use console::Term;
 use failure::Error;
-use lc3::vm::{MCR, VM};
-use std::io::Read;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::{KBSR, MCR, VM};
+use std::io::{empty, ErrorKind, Read, Result as IOResult, Write};
+
+/// 실행을 언제 멈출지 정합니다.
+/// VM이 꺼지거나 breakpoint/watchpoint에 걸리면 항상 멈춥니다.
+#[derive(Clone, Copy)]
+pub enum RunUntil {
+    /// 최대 n개의 instruction을 실행합니다.
+    Limit(usize),
+    /// JSR/JSRR/TRAP을 하나의 instruction처럼 취급해 한 instruction을 실행합니다.
+    /// 호출했다면 그 다음 주소(돌아올 주소)에 도착할 때까지 실행합니다.
+    StepOver,
+    /// 현재 서브루틴에서 빠져나올 때까지 실행합니다.
+    /// 호출한 instruction의 다음 주소가 아닌 곳으로 돌아가더라도 호출 스택이 얕아지면 멈춥니다.
+    StepOut,
+}
+
+/// instruction이 호출 깊이를 얼마나 바꾸는지 반환합니다.
+/// JSR/JSRR/TRAP은 1, RET(JMP R7)/RTI는 -1입니다.
+pub fn call_depth_delta(instruction: u16) -> isize {
+    match Instruction::from_u16(instruction) {
+        Instruction::JSR { .. } | Instruction::JSRR { .. } | Instruction::TRAP { .. } => 1,
+        Instruction::JMP { base: 7 } | Instruction::RTI => -1,
+        _ => 0,
+    }
+}
+
+/// 실행이 멈춘 이유
+pub enum Stop {
+    /// VM이 꺼졌습니다.
+    Halted,
+    /// until 조건을 만족했습니다.
+    Finished,
+    /// 프로그램이 입력을 기다리지만 남은 입력이 없습니다. interactive가 아닐 때만 이 이유로 멈춥니다.
+    NeedInput(u16),
+    /// 실행하려던 instruction의 주소와 그 instruction이 일으킬 실행 오류
+    Fault(u16, Fault),
+    /// breakpoint 또는 Ctrl+C로 멈췄습니다.
+    Paused(String),
+    /// watchpoint에 걸린 instruction을 실행한 직후에 멈췄습니다.
+    /// reverse-continue는 이때 방금 걸린 watchpoint를 건너뜁니다.
+    Watch(String),
+    /// 그 외의 오류로 멈췄습니다.
+    Error(String),
+}
+
+impl Stop {
+    /// 멈춘 이유를 출력합니다. VM이 꺼졌거나 until 조건을 만족했다면 아무것도 출력하지 않습니다.
+    pub fn report(&self, symbol_table: &[TableEntry], term: &Term) -> IOResult<Status> {
+        match self {
+            Stop::Halted | Stop::Finished => Ok(Status::Done),
+            Stop::NeedInput(pc) => report_error(
+                term,
+                &format!(
+                    "프로그램이 입력을 기다리지만 남은 입력이 없어 멈췄습니다: x{:04X} ({})",
+                    pc,
+                    symbol_table_query(symbol_table, *pc as usize)
+                ),
+            ),
+            Stop::Fault(pc, fault) => report_error(
+                term,
+                &format!(
+                    "실행을 멈췄습니다: x{:04X} ({}): {}",
+                    pc,
+                    symbol_table_query(symbol_table, *pc as usize),
+                    fault
+                ),
+            ),
+            Stop::Paused(message) | Stop::Watch(message) => {
+                term.write_line(message)?;
+                Ok(Status::Done)
+            }
+            Stop::Error(message) => report_error(term, message),
+        }
+    }
+}
+
+/// execute의 결과
+pub struct Run {
+    /// 실행한 instruction의 수
+    pub instructions: usize,
+    pub stop: Stop,
+    /// trace 파일에 쓰지 못했는지 여부. 오류는 execute가 이미 출력했습니다.
+    pub trace_failed: bool,
+}
+
+impl Run {
+    /// 멈춘 이유를 출력하고, 명령어가 실패했는지를 반환합니다.
+    pub fn report(&self, symbol_table: &[TableEntry], term: &Term) -> IOResult<Status> {
+        let status = self.stop.report(symbol_table, term)?;
+        Ok(if self.trace_failed {
+            Status::Failed
+        } else {
+            status
+        })
+    }
+}
+
+/// 실행하는 동안 함께 참조하고 갱신하는 디버거의 상태
+pub struct RunContext<'a> {
+    pub pre_input: &'a mut Vec<u8>,
+    pub undo_buffer: Option<&'a mut UndoBuffer>,
+    pub breakpoints: &'a mut BreakpointSet,
+    pub symbol_table: &'a [TableEntry],
+    /// 프로그램의 출력을 모으는 버퍼
+    pub output: &'a mut OutputBuffer,
+    /// false라면 미리 설정된 입력이 떨어졌을 때 터미널에서 읽지 않고 실행을 멈춥니다.
+    pub interactive: bool,
+    /// 실행한 instruction을 기록할 trace 파일
+    pub trace: Option<&'a mut Tracer>,
+    /// 실행한 JSR/JSRR/TRAP과 RET으로 갱신하는 호출 스택
+    pub call_stack: &'a mut CallStack,
+    /// instruction을 실행하기 전에 실행 오류를 검사합니다.
+    pub faults: &'a FaultDetector,
+    /// 초기화되지 않은 레지스터와 메모리를 읽는지 검사합니다.
+    pub uninit: &'a mut UninitTracker,
+    /// enabled라면 실행한 instruction의 수를 셉니다.
+    pub profiler: &'a mut Profiler,
+    /// enabled라면 실행한 instruction과 분기 방향을 기록합니다.
+    pub coverage: &'a mut Coverage,
+    /// 같은 상태가 반복되거나 입출력 없이 좁은 루프를 돌면 멈춥니다.
+    pub loop_detector: &'a mut LoopDetector,
+}
 
 pub fn run_command(
     vm: &mut VM,
     term: &mut Term,
     body: Option<&str>,
-    pre_input: &mut [u8],
-    undo_buffer: Option<&mut UndoBuffer>,
-) -> Result<usize, Error> {
-    if let Some(limit) = body {
-        match limit.parse::<usize>() {
-            Ok(n) => Ok(match undo_buffer {
-                None => vm.run_n(
-                    &mut pre_input.chain(TermWrapper(&mut term.clone())),
-                    &mut TermWrapper(&mut term.clone()),
-                    n,
-                ),
-                Some(buffer) => {
-                    let mut count = 0usize;
-                    while count < n && vm.mem[MCR] >> 15 > 0 {
-                        buffer.push(vm);
-                        assert_eq!(
-                            vm.run_n(
-                                &mut pre_input.chain(TermWrapper(&mut term.clone())),
-                                &mut TermWrapper(&mut term.clone()),
-                                1,
-                            ),
-                            1
-                        );
-                        count += 1;
-                    }
-                    count
-                }
-            }),
+    ctx: RunContext,
+) -> Result<Run, Error> {
+    let limit = match body {
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(n) => n,
             Err(err) => {
-                term.write_line(&format!("잘못된 입력입니다: {}", err))?;
-                Ok(0)
+                return Ok(Run {
+                    instructions: 0,
+                    stop: Stop::Error(format!("잘못된 입력입니다: {}", err)),
+                    trace_failed: false,
+                })
             }
-        }
+        },
+        None => usize::MAX,
+    };
+    execute(vm, term, RunUntil::Limit(limit), ctx)
+}
+
+/// until 조건이 만족될 때까지 instruction을 하나씩 실행하고, 실행한 instruction의 개수와 멈춘 이유를 반환합니다.
+/// 멈춘 이유는 출력하지 않으므로, 필요하다면 `Stop::report`로 출력합니다.
+pub fn execute(
+    vm: &mut VM,
+    term: &mut Term,
+    until: RunUntil,
+    ctx: RunContext,
+) -> Result<Run, Error> {
+    let RunContext {
+        pre_input,
+        mut undo_buffer,
+        breakpoints,
+        symbol_table,
+        output,
+        interactive,
+        mut trace,
+        call_stack,
+        faults,
+        uninit,
+        profiler,
+        coverage,
+        loop_detector,
+    } = ctx;
+    let mut term_input = term.clone();
+    let mut output = output.writer(term);
+    let mut term_input: Box<dyn Read> = if interactive {
+        Box::new(TermWrapper(&mut term_input))
     } else {
-        Ok(match undo_buffer {
-            None => vm.run(
-                &mut pre_input.chain(TermWrapper(&mut term.clone())),
-                &mut TermWrapper(&mut term.clone()),
-            ),
-            Some(buffer) => {
-                let mut count = 0usize;
-                while vm.mem[MCR] >> 15 > 0 {
-                    buffer.push(vm);
-                    assert_eq!(
-                        vm.run_n(
-                            &mut pre_input.chain(TermWrapper(&mut term.clone())),
-                            &mut TermWrapper(&mut term.clone()),
-                            1,
-                        ),
-                        1
-                    );
-                    count += 1;
+        Box::new(empty())
+    };
+    // pre_input 중 프로그램이 이미 읽어간 바이트의 수
+    let mut consumed = 0usize;
+    let mut count = 0usize;
+    // next/finish가 멈출 곳. 재귀 호출이 같은 주소로 돌아가는 경우를 구분하기 위해 호출 스택의 깊이도 확인합니다.
+    let start_depth = call_stack.frames().len();
+    let return_addr = match until {
+        RunUntil::StepOver => match Instruction::from_u16(vm.mem[vm.pc as usize]) {
+            Instruction::JSR { .. } | Instruction::JSRR { .. } | Instruction::TRAP { .. } => {
+                Some(vm.pc.wrapping_add(1))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    if let RunUntil::StepOut = until {
+        let refusal = if call_stack.is_stale() {
+            Some("undo 등으로 VM을 되돌려 호출 스택이 정확하지 않으므로 finish를 할 수 없습니다.")
+        } else if start_depth == 0 {
+            Some("서브루틴 안이 아니므로 finish를 할 수 없습니다. (bt로 호출 스택을 확인할 수 있습니다.)")
+        } else {
+            None
+        };
+        if let Some(message) = refusal {
+            return Ok(Run {
+                instructions: 0,
+                stop: Stop::Error(message.to_owned()),
+                trace_failed: false,
+            });
+        }
+    }
+    loop_detector.start();
+    // 명령어를 입력하는 동안 눌린 Ctrl+C는 무시합니다.
+    take_interrupt();
+    let stop = loop {
+        if vm.mem[MCR] >> 15 == 0 {
+            break Stop::Halted;
+        }
+        match until {
+            RunUntil::Limit(n) if count >= n => break Stop::Finished,
+            RunUntil::StepOver
+                if count > 0
+                    && return_addr.is_none_or(|addr| {
+                        vm.pc == addr && call_stack.frames().len() <= start_depth
+                    }) =>
+            {
+                break Stop::Finished
+            }
+            // 다른 곳으로 돌아가더라도, 서브루틴에서 빠져나왔다면 멈춥니다.
+            RunUntil::StepOut if call_stack.frames().len() < start_depth => break Stop::Finished,
+            _ => (),
+        }
+        if take_interrupt() {
+            break interrupted(vm, symbol_table);
+        }
+
+        // 첫 instruction은 검사하지 않아야 breakpoint에서 멈춘 뒤 다시 run할 수 있습니다.
+        if count > 0 {
+            if let Some(id) = breakpoints.hit(vm) {
+                break Stop::Paused(format!(
+                    "{}번 breakpoint에서 멈췄습니다: x{:04X} ({})",
+                    id,
+                    vm.pc,
+                    symbol_table_query(symbol_table, vm.pc as usize)
+                ));
+            }
+        }
+        let pc = vm.pc as usize;
+        let access = memory_access(vm);
+        if let Some(fault) = faults.check(vm, &access) {
+            break Stop::Fault(vm.pc, fault);
+        }
+        if let Some(message) = uninit.check(vm, &access, symbol_table) {
+            output.finish_line()?;
+            if uninit.mode == UninitMode::Stop {
+                break Stop::Error(format!("실행을 멈췄습니다: {}", message));
+            }
+            term.write_line(&format!("경고: {}", message))?;
+        }
+        let waits_input = access.reads.contains(&KBSR) && consumed == pre_input.len();
+        if !interactive && waits_input {
+            break Stop::NeedInput(vm.pc);
+        }
+        // 입력을 기다리는 동안 VM 안에서 막히면 Ctrl+C로 멈출 수 없으므로, 키를 먼저 읽어 둡니다.
+        // Ctrl+C가 눌리면 instruction을 실행하지 않고 멈추며, 다른 키는 프로그램의 입력이 됩니다.
+        if interactive && waits_input {
+            output.flush()?;
+            match term.read_char() {
+                Ok(ch) => pre_input.push(ch as u8),
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => {
+                    take_interrupt();
+                    break interrupted(vm, symbol_table);
                 }
-                count
+                Err(err) => return Err(err.into()),
             }
-        })
+        }
+        if let Some(infinite) = loop_detector.check(vm, &access) {
+            break Stop::Error(format!(
+                "실행을 멈췄습니다: {}",
+                infinite.describe(symbol_table)
+            ));
+        }
+        let watch_hits = breakpoints.watch_hits(vm, &access);
+        let instruction = vm.mem[pc];
+        if let Some(buffer) = undo_buffer.as_mut() {
+            buffer.push(vm);
+        }
+        uninit.update(vm, &access);
+        if coverage.enabled {
+            coverage.record(vm);
+        }
+        let trace_entry = trace.as_ref().map(|_| TraceEntry::before(vm, access.write));
+        let mut remaining = &pre_input[consumed..];
+        vm.step(&mut (&mut remaining).chain(&mut term_input), &mut output);
+        consumed = pre_input.len() - remaining.len();
+        count += 1;
+        call_stack.after_step(pc as u16, instruction, vm);
+        if profiler.enabled {
+            profiler.record(pc as u16, call_stack.frames());
+        }
+
+        if let (Some(tracer), Some(entry)) = (trace.as_mut(), trace_entry) {
+            if let Err(err) = tracer.record(&entry, vm, symbol_table) {
+                break Stop::Error(format!("trace 파일에 쓰지 못했습니다: {}", err));
+            }
+        }
+
+        let watch_hits = breakpoints.confirm_watch_hits(vm, watch_hits);
+        if !watch_hits.is_empty() {
+            let messages = watch_hits
+                .iter()
+                .map(|hit| {
+                    format!(
+                        "{}번 watchpoint: x{:04X} ({}) {} x{:04X} -> x{:04X}, PC: x{:04X} ({})",
+                        hit.id,
+                        hit.addr,
+                        symbol_table_query(symbol_table, hit.addr),
+                        if hit.write { "쓰기" } else { "읽기" },
+                        hit.old,
+                        vm.mem[hit.addr],
+                        pc,
+                        symbol_table_query(symbol_table, pc)
+                    )
+                })
+                .collect::<Vec<_>>();
+            break Stop::Watch(messages.join("\n"));
+        }
+    };
+
+    output.finish_line()?;
+    let mut trace_failed = false;
+    if let Some(tracer) = trace {
+        if let Err(err) = tracer.flush() {
+            term.write_line(&format!("trace 파일에 쓰지 못했습니다: {}", err))?;
+            trace_failed = true;
+        }
     }
+
+    // 프로그램이 읽어간 만큼 미리 설정된 입력을 소모합니다.
+    pre_input.drain(..consumed);
+    Ok(Run {
+        instructions: count,
+        stop,
+        trace_failed,
+    })
+}
+
+fn interrupted(vm: &VM, symbol_table: &[TableEntry]) -> Stop {
+    Stop::Paused(format!(
+        "Ctrl+C로 실행을 멈췄습니다: x{:04X} ({})",
+        vm.pc,
+        symbol_table_query(symbol_table, vm.pc as usize)
+    ))
 }
@@ -0,0 +1,208 @@
+use crate::expr::{cc_mask, Expr};
+use crate::helper::{report_error, Status};
+use crate::symbol::{symbol_table_query, TableEntry};
+use crate::undo::UndoBuffer;
+use crate::uninit::UninitTracker;
+use console::Term;
+use lc3::vm::instruction::Condition;
+use lc3::vm::VM;
+use std::io::Result as IOResult;
+
+/// set 명령어로 값을 바꿀 수 있는 대상
+enum Target {
+    Register(usize),
+    Pc,
+    Psr,
+    Cc,
+    Supervisor,
+    Mem(Expr),
+}
+
+/// "<target> [=] <value>" 형태의 입력을 대상과 값 문자열로 나눕니다.
+fn split_target(body: &str) -> Option<(&str, &str)> {
+    let body = body.trim();
+    let idx = if body
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("mem["))
+    {
+        body.find(']')? + 1
+    } else {
+        body.find(|c: char| c.is_whitespace() || c == '=')?
+    };
+    let value = body[idx..].trim_start();
+    let value = value.strip_prefix('=').unwrap_or(value).trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some((body[..idx].trim(), value))
+    }
+}
+
+fn parse_target(target: &str, symbol_table: &[TableEntry]) -> Result<Target, String> {
+    let lower = target.to_ascii_lowercase();
+    match lower.as_str() {
+        "pc" => return Ok(Target::Pc),
+        "psr" => return Ok(Target::Psr),
+        "cc" => return Ok(Target::Cc),
+        "supervisor" => return Ok(Target::Supervisor),
+        _ => (),
+    }
+    if lower.len() == 2 && lower.starts_with('r') {
+        if let Ok(n @ 0..=7) = lower[1..].parse::<usize>() {
+            return Ok(Target::Register(n));
+        }
+    }
+    if lower.starts_with("mem[") && lower.ends_with(']') {
+        return Ok(Target::Mem(Expr::parse(
+            &target[4..target.len() - 1],
+            symbol_table,
+        )?));
+    }
+    Err(format!("값을 바꿀 수 없는 대상입니다: {}", target))
+}
+
+/// 식의 값을 16비트 word로 바꿉니다. 부호 있는/없는 16비트 범위를 벗어나면 실패합니다.
+fn eval_word(value: &str, vm: &VM, symbol_table: &[TableEntry]) -> Result<u16, String> {
+    let n = Expr::parse(value, symbol_table)?.eval(vm);
+    if n < i32::from(i16::MIN) || n > i32::from(u16::MAX) {
+        return Err(format!("16비트 범위를 벗어나는 값입니다: {}", n));
+    }
+    Ok(n as u16)
+}
+
+fn apply(
+    vm: &mut VM,
+    target: &Target,
+    value: &str,
+    symbol_table: &[TableEntry],
+) -> Result<String, String> {
+    match target {
+        Target::Register(n) => {
+            vm.register[*n] = eval_word(value, vm, symbol_table)? as i16;
+            Ok(format!(
+                "r{} = x{:04X} ({})",
+                n, vm.register[*n], vm.register[*n]
+            ))
+        }
+        Target::Pc => {
+            vm.pc = eval_word(value, vm, symbol_table)?;
+            Ok(format!(
+                "PC = x{:04X} ({})",
+                vm.pc,
+                symbol_table_query(symbol_table, vm.pc as usize)
+            ))
+        }
+        Target::Psr => {
+            let psr = eval_word(value, vm, symbol_table)?;
+            vm.supervisor = psr >> 15 == 0;
+            vm.priority = ((psr >> 8) & 0b111) as u8;
+            vm.condition = Condition {
+                n: psr & 0b100 != 0,
+                z: psr & 0b010 != 0,
+                p: psr & 0b001 != 0,
+            };
+            Ok(format!("PSR = x{:04X}", vm.psr()))
+        }
+        Target::Cc => {
+            let mask = cc_mask(value).ok_or(format!("잘못된 CC 플래그입니다: {}", value))?;
+            vm.condition = Condition {
+                n: mask & 0b100 != 0,
+                z: mask & 0b010 != 0,
+                p: mask & 0b001 != 0,
+            };
+            Ok(format!("CC = {}", value))
+        }
+        Target::Supervisor => {
+            vm.supervisor = match value.to_ascii_lowercase().as_str() {
+                "yes" | "on" | "true" | "1" => true,
+                "no" | "off" | "false" | "0" => false,
+                _ => return Err(format!("yes 또는 no를 입력하세요: {}", value)),
+            };
+            Ok(format!(
+                "Supervisor = {}",
+                if vm.supervisor { "yes" } else { "no" }
+            ))
+        }
+        Target::Mem(addr) => {
+            let addr = addr.eval(vm) as u16 as usize;
+            vm.mem[addr] = eval_word(value, vm, symbol_table)?;
+            Ok(format!(
+                "mem[x{:04X}] ({}) = x{:04X} ({})",
+                addr,
+                symbol_table_query(symbol_table, addr),
+                vm.mem[addr],
+                vm.mem[addr] as i16
+            ))
+        }
+    }
+}
+
+/// set <target> <value>: 레지스터, PC, PSR, CC, supervisor 플래그 또는 메모리 값을 바꿉니다.
+/// undo buffer가 있다면 바꾸기 전의 상태를 기록합니다. 이 기록은 instruction으로 세지 않고,
+/// 바로 앞에 실행한 instruction을 undo할 때 함께 되돌립니다.
+pub fn set_command(
+    vm: &mut VM,
+    undo_buffer: Option<&mut UndoBuffer>,
+    uninit: &mut UninitTracker,
+    symbol_table: &[TableEntry],
+    term: &Term,
+    body: Option<&str>,
+) -> IOResult<Status> {
+    let (target, value) = match body.and_then(split_target) {
+        Some(split) => split,
+        None => return report_error(term, "사용법: set <target> <value>"),
+    };
+    let target = match parse_target(target, symbol_table) {
+        Ok(target) => target,
+        Err(err) => return report_error(term, &err),
+    };
+
+    let mut new_vm = vm.clone();
+    match apply(&mut new_vm, &target, value, symbol_table) {
+        Ok(result) => {
+            let addrs = match &target {
+                Target::Mem(addr) => vec![addr.eval(vm) as u16 as usize],
+                _ => vec![],
+            };
+            match &target {
+                Target::Register(n) => uninit.define_register(*n),
+                Target::Mem(_) => uninit.define_memory(addrs[0]),
+                _ => (),
+            }
+            if let Some(buffer) = undo_buffer {
+                buffer.push_change(vm, &addrs);
+            }
+            *vm = new_vm;
+            term.write_line(&result)?;
+            Ok(Status::Done)
+        }
+        Err(err) => report_error(term, &format!("값을 바꾸지 못했습니다: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_target_and_value() {
+        assert_eq!(split_target("r1 5"), Some(("r1", "5")));
+        assert_eq!(split_target("  pc = x3000 "), Some(("pc", "x3000")));
+        assert_eq!(
+            split_target("MEM[x3000 + 1]=#-1"),
+            Some(("MEM[x3000 + 1]", "#-1"))
+        );
+        assert_eq!(split_target("r1"), None);
+        assert_eq!(split_target("r1 ="), None);
+    }
+
+    #[test]
+    fn non_ascii_argument_is_an_error() {
+        assert_eq!(split_target("가나 1"), Some(("가나", "1")));
+        assert_eq!(split_target("가"), None);
+        assert_eq!(split_target("m가[x3000] 1"), Some(("m가[x3000]", "1")));
+        assert!(parse_target("가나", &[]).is_err());
+        assert!(parse_target("r가", &[]).is_err());
+        assert!(parse_target("mem[가나]", &[]).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::symbol::{symbol_table_postprocess, TableEntry};
+use crate::undo::UndoBuffer;
+use console::Term;
+use lc3::vm::instruction::Condition;
+use lc3::vm::VM;
+use std::convert::TryInto;
+use std::io::Result as IOResult;
+
+/// 스냅샷 파일의 시작을 나타내는 8바이트
+const MAGIC: &[u8; 8] = b"LC3DBGSN";
+/// 스냅샷 형식의 버전. 형식이 바뀌면 올려야 합니다.
+const VERSION: u16 = 1;
+
+/// 디스크에 저장되는 디버거 상태.
+/// 파일 형식(모든 정수는 big-endian):
+///   MAGIC(8) VERSION(u16)
+///   R0~R7(u16 x 8) PC(u16) IR(u16) 플래그(u8: supervisor, n, z, p 순서로 bit 3~0) priority(u8)
+///   메모리(u16 x 65536, 장치 레지스터와 스택 포함)
+///   심볼 개수(u32), 각 심볼마다 주소(u16) 이름 길이(u16) 이름(UTF-8)
+///   미리 설정된 입력의 길이(u32), 입력
+pub struct Snapshot {
+    pub vm: VM,
+    /// (주소, 심볼 이름)
+    pub symbols: Vec<(u16, String)>,
+    pub pre_input: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn new(vm: &VM, symbol_table: &[TableEntry], pre_input: &[u8]) -> Snapshot {
+        Snapshot {
+            vm: vm.clone(),
+            symbols: symbol_table
+                .iter()
+                .enumerate()
+                .filter_map(|(addr, entry)| match entry {
+                    TableEntry::Symbol(name) => Some((addr as u16, name.clone())),
+                    _ => None,
+                })
+                .collect(),
+            pre_input: pre_input.to_vec(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let vm = &self.vm;
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&VERSION.to_be_bytes());
+        for &r in vm.register.iter() {
+            out.extend_from_slice(&r.to_be_bytes());
+        }
+        out.extend_from_slice(&vm.pc.to_be_bytes());
+        out.extend_from_slice(&vm.ir.to_be_bytes());
+        out.push(
+            (vm.supervisor as u8) << 3
+                | (vm.condition.n as u8) << 2
+                | (vm.condition.z as u8) << 1
+                | vm.condition.p as u8,
+        );
+        out.push(vm.priority);
+        for &word in vm.mem.iter() {
+            out.extend_from_slice(&word.to_be_bytes());
+        }
+        out.extend_from_slice(&(self.symbols.len() as u32).to_be_bytes());
+        for (addr, name) in self.symbols.iter() {
+            out.extend_from_slice(&addr.to_be_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            out.extend_from_slice(name.as_bytes());
+        }
+        out.extend_from_slice(&(self.pre_input.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.pre_input);
+        out
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Snapshot, String> {
+        if !data.starts_with(MAGIC) {
+            return Err("lc3dbg 스냅샷 파일이 아닙니다.".to_owned());
+        }
+        let mut reader = Reader {
+            data,
+            pos: MAGIC.len(),
+        };
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(format!(
+                "지원하지 않는 스냅샷 버전입니다: {} (지원하는 버전: {})",
+                version, VERSION
+            ));
+        }
+
+        let mut vm = VM::new();
+        for r in vm.register.iter_mut() {
+            *r = reader.u16()? as i16;
+        }
+        vm.pc = reader.u16()?;
+        vm.ir = reader.u16()?;
+        let flags = reader.take(1)?[0];
+        vm.supervisor = flags & 0b1000 != 0;
+        vm.condition = Condition {
+            n: flags & 0b100 != 0,
+            z: flags & 0b010 != 0,
+            p: flags & 0b001 != 0,
+        };
+        vm.priority = reader.take(1)?[0];
+        for word in vm.mem.iter_mut() {
+            *word = reader.u16()?;
+        }
+
+        let count = reader.u32()?;
+        let mut symbols = vec![];
+        for _ in 0..count {
+            let addr = reader.u16()?;
+            let len = reader.u16()? as usize;
+            let name = String::from_utf8(reader.take(len)?.to_vec())
+                .map_err(|_| "심볼 이름이 올바른 UTF-8이 아닙니다.".to_owned())?;
+            symbols.push((addr, name));
+        }
+        let len = reader.u32()? as usize;
+        let pre_input = reader.take(len)?.to_vec();
+        if reader.pos != data.len() {
+            return Err("스냅샷 뒤에 알 수 없는 데이터가 있습니다.".to_owned());
+        }
+        Ok(Snapshot {
+            vm,
+            symbols,
+            pre_input,
+        })
+    }
+
+    /// 스냅샷의 심볼들로 symbol_table을 새로 채웁니다.
+    pub fn fill_symbol_table(&self, symbol_table: &mut [TableEntry]) {
+        for entry in symbol_table.iter_mut() {
+            *entry = TableEntry::Unknown;
+        }
+        for (addr, name) in self.symbols.iter() {
+            symbol_table[*addr as usize] = TableEntry::Symbol(name.clone());
+        }
+        symbol_table_postprocess(symbol_table);
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < n {
+            return Err("스냅샷 파일이 잘렸습니다.".to_owned());
+        }
+        self.pos += n;
+        Ok(&self.data[self.pos - n..self.pos])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// path의 스냅샷 파일을 읽습니다.
+pub fn load(path: &str) -> Result<Snapshot, String> {
+    let data = std::fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    Snapshot::decode(&data).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// snapshot save <file> / snapshot load <file>: VM, 심볼 테이블, 미리 설정된 입력을 파일로 저장하거나 불러옵니다.
+/// 불러오면 undo buffer의 기록은 지워집니다. 스냅샷을 불러왔다면 Some(true), 저장했다면 Some(false),
+/// 실패했다면 None을 반환합니다.
+pub fn snapshot_command(
+    vm: &mut VM,
+    symbol_table: &mut [TableEntry],
+    pre_input: &mut Vec<u8>,
+    undo_buffer: Option<&mut UndoBuffer>,
+    term: &Term,
+    body: Option<&str>,
+) -> IOResult<Option<bool>> {
+    let body = body.unwrap_or("").trim();
+    let (sub, path) = match body.find(' ') {
+        Some(idx) => (&body[..idx], body[idx + 1..].trim()),
+        None => (body, ""),
+    };
+    match (sub, path) {
+        (_, "") => term
+            .write_line("사용법: snapshot save|load <file>")
+            .map(|_| None),
+        ("save", path) => {
+            let data = Snapshot::new(vm, symbol_table, pre_input).encode();
+            match std::fs::write(path, &data) {
+                Ok(()) => term.write_line(&format!(
+                    "스냅샷을 저장했습니다: {} ({}바이트)",
+                    path,
+                    data.len()
+                ))?,
+                Err(err) => {
+                    term.write_line(&format!("저장하지 못했습니다: {}: {}", path, err))?;
+                    return Ok(None);
+                }
+            }
+            Ok(Some(false))
+        }
+        ("load", path) => match load(path) {
+            Ok(snapshot) => {
+                *vm = snapshot.vm.clone();
+                snapshot.fill_symbol_table(symbol_table);
+                *pre_input = snapshot.pre_input;
+                if let Some(buffer) = undo_buffer {
+                    buffer.clear();
+                }
+                term.write_line(&format!("스냅샷을 불러왔습니다: {}", path))?;
+                Ok(Some(true))
+            }
+            Err(err) => {
+                term.write_line(&format!("스냅샷을 불러오지 못했습니다: {}", err))?;
+                Ok(None)
+            }
+        },
+        _ => term
+            .write_line("사용법: snapshot save|load <file>")
+            .map(|_| None),
+    }
+}
@@ -0,0 +1,91 @@
+use console::Term;
+use std::collections::HashMap;
+use std::io::Result as IOResult;
+use std::path::Path;
+
+/// 컨텍스트로 보여줄 앞뒤 줄 수
+const CONTEXT_LINES: usize = 2;
+
+struct SourceFile {
+    name: String,
+    lines: Vec<String>,
+}
+
+/// 메모리 주소와 어셈블리 소스의 줄을 서로 찾을 수 있게 하는 표.
+/// 내장 어셈블러의 결과(`asm::Assembly::lines`)로 만들어집니다.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// 주소 -> (파일 번호, 줄 번호)
+    lines: HashMap<u16, (usize, usize)>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        Default::default()
+    }
+
+    /// 소스 파일 하나와, 그 파일로부터 어셈블된 (주소, 줄 번호) 목록을 추가합니다.
+    pub fn add_file(&mut self, name: &str, source: &str, lines: &[(u16, usize)]) {
+        let file = self.files.len();
+        self.files.push(SourceFile {
+            name: name.to_owned(),
+            lines: source.lines().map(str::to_owned).collect(),
+        });
+        for &(addr, line) in lines.iter() {
+            self.lines.insert(addr, (file, line));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// addr에 해당하는 "file.asm:42" 형태의 위치를 반환합니다.
+    pub fn location(&self, addr: usize) -> Option<String> {
+        let &(file, line) = self.lines.get(&(addr as u16))?;
+        Some(format!("{}:{}", self.files[file].name, line))
+    }
+
+    /// addr에 해당하는 소스 줄의 내용을 반환합니다.
+    pub fn line_text(&self, addr: usize) -> Option<&str> {
+        let &(file, line) = self.lines.get(&(addr as u16))?;
+        self.files[file].lines.get(line - 1).map(String::as_str)
+    }
+
+    /// "file.asm:42" 형태의 위치를 주소로 바꿉니다.
+    /// 해당 줄에 코드가 없으면 그 뒤로 가장 먼저 코드가 있는 줄의 주소를 반환합니다.
+    pub fn address_of(&self, location: &str) -> Option<usize> {
+        let idx = location.rfind(':')?;
+        let (name, line) = (&location[..idx], location[idx + 1..].parse::<usize>().ok()?);
+        let file = self.files.iter().position(|file| {
+            file.name == name || Path::new(&file.name).file_name() == Some(name.as_ref())
+        })?;
+        self.lines
+            .iter()
+            .filter(|(_, &(f, l))| f == file && l >= line)
+            .min_by_key(|(&addr, &(_, l))| (l, addr))
+            .map(|(&addr, _)| addr as usize)
+    }
+
+    /// addr에 해당하는 소스 위치와 앞뒤 몇 줄을 출력합니다. 소스 정보가 없으면 아무것도 출력하지 않습니다.
+    pub fn print_context(&self, addr: usize, term: &Term) -> IOResult<()> {
+        let &(file, line) = match self.lines.get(&(addr as u16)) {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        let file = &self.files[file];
+        term.write_line(&format!("{}:{}", file.name, line))?;
+        let first = line.saturating_sub(CONTEXT_LINES).max(1);
+        let last = (line + CONTEXT_LINES).min(file.lines.len());
+        for n in first..=last {
+            term.write_line(&format!(
+                "{} {:>4} | {}",
+                if n == line { "=>" } else { "  " },
+                n,
+                file.lines[n - 1]
+            ))?;
+        }
+        Ok(())
+    }
+}
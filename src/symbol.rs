@@ -91,13 +91,30 @@ pub fn symbol_table_query(symbol_table: &[TableEntry], addr: usize) -> String {
     }
 }
 
+/// 이름으로 심볼의 주소를 찾습니다. 대소문자가 정확히 일치하는 심볼을 우선합니다.
+pub fn symbol_table_lookup(symbol_table: &[TableEntry], name: &str) -> Option<usize> {
+    let symbols = || {
+        symbol_table
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, entry)| match entry {
+                TableEntry::Symbol(sym) => Some((addr, sym)),
+                _ => None,
+            })
+    };
+    symbols()
+        .find(|(_, sym)| sym.as_str() == name)
+        .or_else(|| symbols().find(|(_, sym)| sym.eq_ignore_ascii_case(name)))
+        .map(|(addr, _)| addr)
+}
+
 pub fn decorate_instruction_str(
     symbol_table: &[TableEntry],
     instruction: &str,
     addr: usize,
 ) -> String {
-    let splits = instruction.clone().split(" ").collect::<Vec<_>>();
-    let decorator = match splits[0] {
+    let splits = instruction.split(' ').collect::<Vec<_>>();
+    match splits[0] {
         "BR" | "BRn" | "BRz" | "BRp" | "BRnz" | "BRzp" | "BRnp" | "BRnzp" | "LD" | "LDI"
         | "LEA" | "ST" | "STI" => {
             String::from(" (")
@@ -109,6 +126,5 @@ pub fn decorate_instruction_str(
                 + ")"
         }
         _ => String::from(""),
-    };
-    decorator
+    }
 }
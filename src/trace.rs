@@ -0,0 +1,286 @@
+use crate::helper::{report_error, Status};
+use crate::symbol::{decorate_instruction_str, symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::fs::File;
+use std::io::{BufWriter, Result as IOResult, Write};
+
+/// trace 파일의 형식
+#[derive(Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// 사람이 읽기 위한 형식
+    Text,
+    /// 한 줄에 한 instruction씩, 첫 줄은 header인 CSV
+    Csv,
+    /// 한 줄에 한 instruction씩 JSON object를 쓰는 JSON Lines
+    Jsonl,
+}
+
+impl TraceFormat {
+    fn parse(s: &str) -> Option<TraceFormat> {
+        match s {
+            "text" => Some(TraceFormat::Text),
+            "csv" => Some(TraceFormat::Csv),
+            "jsonl" | "json" => Some(TraceFormat::Jsonl),
+            _ => None,
+        }
+    }
+
+    /// 파일 이름의 확장자로 형식을 정합니다. 알 수 없는 확장자는 Text입니다.
+    fn from_path(path: &str) -> TraceFormat {
+        match path.rsplit('.').next() {
+            Some("csv") => TraceFormat::Csv,
+            Some("jsonl") | Some("json") => TraceFormat::Jsonl,
+            _ => TraceFormat::Text,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            TraceFormat::Text => "text",
+            TraceFormat::Csv => "csv",
+            TraceFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// instruction 하나를 실행하기 전의 상태 중 trace에 필요한 부분
+pub struct TraceEntry {
+    pc: u16,
+    instruction: u16,
+    register: [i16; 8],
+    /// (주소, 쓰기 전의 값)
+    write: Option<(usize, u16)>,
+}
+
+impl TraceEntry {
+    /// PC의 instruction을 실행하기 전에 호출합니다. write는 instruction이 쓸 메모리 주소입니다.
+    pub fn before(vm: &VM, write: Option<usize>) -> TraceEntry {
+        TraceEntry {
+            pc: vm.pc,
+            instruction: vm.mem[vm.pc as usize],
+            register: vm.register,
+            write: write.map(|addr| (addr, vm.mem[addr])),
+        }
+    }
+}
+
+/// 실행한 instruction을 한 줄씩 파일에 기록합니다.
+pub struct Tracer {
+    writer: BufWriter<File>,
+    format: TraceFormat,
+    path: String,
+    /// 지금까지 기록한 instruction의 수
+    count: usize,
+}
+
+impl Tracer {
+    pub fn create(path: &str, format: TraceFormat) -> IOResult<Tracer> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if format == TraceFormat::Csv {
+            writeln!(writer, "seq,pc,symbol,instruction,registers,memory,cc")?;
+        }
+        Ok(Tracer {
+            writer,
+            format,
+            path: path.to_owned(),
+            count: 0,
+        })
+    }
+
+    /// entry의 instruction을 실행한 후의 vm과 비교해 한 줄을 기록합니다.
+    pub fn record(
+        &mut self,
+        entry: &TraceEntry,
+        vm: &VM,
+        symbol_table: &[TableEntry],
+    ) -> IOResult<()> {
+        self.count += 1;
+        let pc = entry.pc as usize;
+        let symbol = symbol_table_query(symbol_table, pc);
+        let instruction = format!("{}", Instruction::from_u16(entry.instruction));
+        let decorated = format!(
+            "{}{}",
+            instruction,
+            decorate_instruction_str(symbol_table, &instruction, pc)
+        );
+        let registers = (0..8)
+            .filter(|&i| entry.register[i] != vm.register[i])
+            .map(|i| (i, entry.register[i] as u16, vm.register[i] as u16))
+            .collect::<Vec<_>>();
+        let write = entry
+            .write
+            .filter(|&(addr, old)| vm.mem[addr] != old)
+            .map(|(addr, old)| (addr, old, vm.mem[addr]));
+        let cc = if vm.condition.n {
+            "n"
+        } else if vm.condition.z {
+            "z"
+        } else {
+            "p"
+        };
+
+        match self.format {
+            TraceFormat::Text => {
+                let mut changes = registers
+                    .iter()
+                    .map(|(i, old, new)| format!("R{}: x{:04X} -> x{:04X}", i, old, new))
+                    .collect::<Vec<_>>();
+                if let Some((addr, old, new)) = write {
+                    changes.push(format!("mem[x{:04X}]: x{:04X} -> x{:04X}", addr, old, new));
+                }
+                changes.push(format!("CC: {}", cc));
+                writeln!(
+                    self.writer,
+                    "#{:<7} x{:04X} {:<16} {:<32} {}",
+                    self.count,
+                    pc,
+                    symbol,
+                    decorated,
+                    changes.join(", ")
+                )
+            }
+            TraceFormat::Csv => {
+                let registers = registers
+                    .iter()
+                    .map(|(i, old, new)| format!("R{}:x{:04X}>x{:04X}", i, old, new))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let memory = write
+                    .map(|(addr, old, new)| format!("x{:04X}:x{:04X}>x{:04X}", addr, old, new))
+                    .unwrap_or_default();
+                writeln!(
+                    self.writer,
+                    "{},x{:04X},{},{},{},{},{}",
+                    self.count,
+                    pc,
+                    csv_field(&symbol),
+                    csv_field(&decorated),
+                    registers,
+                    memory,
+                    cc
+                )
+            }
+            TraceFormat::Jsonl => {
+                let registers = registers
+                    .iter()
+                    .map(|(i, old, new)| {
+                        format!("{{\"reg\":{},\"old\":{},\"new\":{}}}", i, old, new)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let memory = write
+                    .map(|(addr, old, new)| {
+                        format!("{{\"addr\":{},\"old\":{},\"new\":{}}}", addr, old, new)
+                    })
+                    .unwrap_or_default();
+                writeln!(
+                    self.writer,
+                    "{{\"seq\":{},\"pc\":{},\"symbol\":{},\"instruction\":{},\"registers\":[{}],\"memory\":[{}],\"cc\":\"{}\"}}",
+                    self.count,
+                    pc,
+                    json_string(&symbol),
+                    json_string(&decorated),
+                    registers,
+                    memory,
+                    cc
+                )
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> IOResult<()> {
+        self.writer.flush()
+    }
+}
+
+/// 쉼표나 따옴표가 있는 CSV 필드를 따옴표로 감쌉니다.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// 따옴표로 감싼 JSON 문자열을 만듭니다.
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// trace on <file> [text|csv|jsonl] / trace off: 실행하는 instruction을 파일에 기록하기 시작하거나 멈춥니다.
+/// 형식을 주지 않으면 파일의 확장자(.csv, .jsonl)로 정합니다.
+pub fn trace_command(
+    tracer: &mut Option<Tracer>,
+    term: &Term,
+    body: Option<&str>,
+) -> IOResult<Status> {
+    let args = body.unwrap_or("").split_whitespace().collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => match tracer {
+            Some(tracer) => term.write_line(&format!(
+                "{}에 {} 형식으로 기록하고 있습니다. ({}개의 instruction)",
+                tracer.path,
+                tracer.format.as_str(),
+                tracer.count
+            ))?,
+            None => term.write_line("trace가 꺼져 있습니다.")?,
+        },
+        ["off"] => match tracer.take() {
+            Some(mut old) => {
+                old.flush()?;
+                term.write_line(&format!(
+                    "trace를 껐습니다: {} ({}개의 instruction)",
+                    old.path, old.count
+                ))?;
+            }
+            None => return report_error(term, "trace가 꺼져 있습니다."),
+        },
+        ["on", path] | ["on", path, _] => {
+            let format = match args.get(2) {
+                Some(format) => match TraceFormat::parse(format) {
+                    Some(format) => format,
+                    None => {
+                        return report_error(
+                            term,
+                            &format!("알 수 없는 형식입니다: {} (text, csv, jsonl)", format),
+                        )
+                    }
+                },
+                None => TraceFormat::from_path(path),
+            };
+            if let Some(mut old) = tracer.take() {
+                old.flush()?;
+            }
+            match Tracer::create(path, format) {
+                Ok(new) => {
+                    *tracer = Some(new);
+                    term.write_line(&format!(
+                        "실행하는 instruction을 {}에 {} 형식으로 기록합니다.",
+                        path,
+                        format.as_str()
+                    ))?;
+                }
+                Err(err) => {
+                    return report_error(
+                        term,
+                        &format!("trace 파일을 만들 수 없습니다: {}: {}", path, err),
+                    )
+                }
+            }
+        }
+        _ => return report_error(term, "사용법: trace [on <file> [text|csv|jsonl]|off]"),
+    }
+    Ok(Status::Done)
+}
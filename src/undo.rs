@@ -1,63 +1,367 @@
+use crate::access::memory_access;
 use console::Term;
-use lc3::vm::VM;
+use lc3::vm::instruction::Condition;
+use lc3::vm::{DSR, KBDR, KBSR, VM};
+use std::collections::VecDeque;
 use std::io::Result as IOResult;
+use std::mem::swap;
 
+/// 기록 하나가 담을 수 있는 메모리 word의 최대 개수.
+/// instruction 하나는 쓰기 대상 하나와 장치 레지스터(KBSR, KBDR, DSR)만 바꿀 수 있습니다.
+const MAX_WORDS: usize = 4;
+
+/// instruction 하나를 실행하기 전의 상태 중, 실행으로 바뀔 수 있는 부분.
+/// swap으로 VM과 값을 맞바꾸면 VM은 이전 상태가 되고 기록에는 이후 상태가 남으므로,
+/// 같은 기록으로 되돌리기와 다시 실행하기를 모두 할 수 있습니다.
+#[derive(Clone)]
+struct Delta {
+    register: [i16; 8],
+    pc: u16,
+    ir: u16,
+    supervisor: bool,
+    priority: u8,
+    condition: Condition,
+    /// (주소, 값)
+    words: [(u16, u16); MAX_WORDS],
+    len: u8,
+    /// instruction 실행이 아닌 방법(set 등)으로 바꾼 기록인지
+    edit: bool,
+}
+
+impl Delta {
+    fn record(vm: &VM, addrs: impl IntoIterator<Item = usize>, edit: bool) -> Delta {
+        let mut delta = Delta {
+            register: vm.register,
+            pc: vm.pc,
+            ir: vm.ir,
+            supervisor: vm.supervisor,
+            priority: vm.priority,
+            condition: vm.condition.clone(),
+            words: [(0, 0); MAX_WORDS],
+            len: 0,
+            edit,
+        };
+        for addr in addrs.into_iter().take(MAX_WORDS) {
+            if !delta.words[..delta.len as usize]
+                .iter()
+                .any(|&(a, _)| a as usize == addr)
+            {
+                delta.words[delta.len as usize] = (addr as u16, vm.mem[addr]);
+                delta.len += 1;
+            }
+        }
+        delta
+    }
+
+    fn swap(&mut self, vm: &mut VM) {
+        swap(&mut self.register, &mut vm.register);
+        swap(&mut self.pc, &mut vm.pc);
+        swap(&mut self.ir, &mut vm.ir);
+        swap(&mut self.supervisor, &mut vm.supervisor);
+        swap(&mut self.priority, &mut vm.priority);
+        swap(&mut self.condition, &mut vm.condition);
+        for (addr, value) in self.words[..self.len as usize].iter_mut() {
+            swap(value, &mut vm.mem[*addr as usize]);
+        }
+    }
+}
+
+/// 최근 size개의 instruction을 되돌릴 수 있는 기록.
+/// VM 전체를 복사하는 대신, 각 instruction이 바꿀 수 있는 레지스터와 메모리 word만 기록합니다.
+/// set 등으로 바꾼 기록은 instruction으로 세지 않습니다. 가장 최근의 기록이라면 undo_edit로 따로 되돌리고,
+/// 그렇지 않다면 바로 앞의 instruction을 되돌릴 때 함께 되돌립니다.
 pub struct UndoBuffer {
-    buf: Vec<VM>,
+    undo: VecDeque<Delta>,
+    /// 되돌린 기록. 가장 최근에 되돌린 것이 마지막에 있습니다.
+    redo: Vec<Delta>,
+    /// undo와 redo에 있는 instruction 기록의 수
+    undo_steps: usize,
+    redo_steps: usize,
     size: usize,
-    ptr: usize,
 }
 
 impl UndoBuffer {
     pub fn new(size: usize) -> UndoBuffer {
         UndoBuffer {
-            buf: Vec::with_capacity(size),
-            size: 0,
-            ptr: 0,
+            undo: VecDeque::new(),
+            redo: vec![],
+            undo_steps: 0,
+            redo_steps: 0,
+            size,
+        }
+    }
+
+    /// 기록 하나가 차지하는 메모리의 크기(바이트)
+    pub fn entry_size() -> usize {
+        std::mem::size_of::<Delta>()
+    }
+
+    fn push_delta(&mut self, delta: Delta) {
+        if self.size == 0 {
+            return;
+        }
+        if self.undo.len() == self.size {
+            if let Some(oldest) = self.undo.pop_front() {
+                if !oldest.edit {
+                    self.undo_steps -= 1;
+                }
+            }
+        }
+        if !delta.edit {
+            self.undo_steps += 1;
         }
+        self.undo.push_back(delta);
+        self.redo.clear();
+        self.redo_steps = 0;
     }
 
+    /// PC의 instruction을 실행하기 전에 호출해, 실행으로 바뀔 상태를 기록합니다.
     pub fn push(&mut self, vm: &VM) {
-        if self.buf.len() == self.ptr {
-            self.buf.push(vm.clone());
-        } else {
-            self.buf[self.ptr] = vm.clone();
+        let addrs = [KBSR, KBDR, DSR].iter().cloned();
+        self.push_delta(Delta::record(
+            vm,
+            addrs.chain(memory_access(vm).write),
+            false,
+        ));
+    }
+
+    /// instruction 실행이 아닌 방법(set 등)으로 레지스터와 addrs의 메모리를 바꾸기 전에 호출합니다.
+    /// 이 기록은 instruction으로 세지 않으며, undo_edit로 따로 되돌릴 수 있습니다.
+    pub fn push_change(&mut self, vm: &VM, addrs: &[usize]) {
+        self.push_delta(Delta::record(vm, addrs.iter().cloned(), true));
+    }
+
+    /// 기록을 모두 지웁니다. 버퍼의 크기는 그대로입니다.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.undo_steps = 0;
+        self.redo_steps = 0;
+    }
+
+    /// 되돌릴 수 있는 instruction의 수
+    pub fn len(&self) -> usize {
+        self.undo_steps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo_steps == 0
+    }
+
+    /// 가장 최근의 기록이 set 등으로 바꾼 기록이라면 그것 하나만 되돌리고 true를 반환합니다.
+    pub fn undo_edit(&mut self, vm: &mut VM) -> bool {
+        if !self.undo.back().is_some_and(|delta| delta.edit) {
+            return false;
+        }
+        if let Some(mut delta) = self.undo.pop_back() {
+            delta.swap(vm);
+            self.redo.push(delta);
         }
-        self.ptr += 1;
-        if self.size < self.buf.capacity() {
-            self.size += 1;
+        true
+    }
+
+    /// 가장 최근에 되돌린 기록이 undo_edit로 되돌린 기록이라면 그것 하나만 다시 적용하고 true를 반환합니다.
+    pub fn redo_edit(&mut self, vm: &mut VM) -> bool {
+        if !self.redo.last().is_some_and(|delta| delta.edit) {
+            return false;
         }
-        if self.ptr == self.buf.capacity() {
-            self.ptr = 0;
+        if let Some(mut delta) = self.redo.pop() {
+            delta.swap(vm);
+            self.undo.push_back(delta);
         }
+        true
     }
 
-    fn undo(&mut self, n: usize) -> Option<&VM> {
-        if self.size < n {
-            return None;
+    /// vm을 instruction 하나만큼 되돌립니다. 그 instruction 뒤에 set 등으로 바꾼 기록도 함께 되돌립니다.
+    /// 되돌릴 instruction이 없으면 false를 반환합니다.
+    pub fn undo_one(&mut self, vm: &mut VM) -> bool {
+        if self.undo_steps == 0 {
+            return false;
         }
+        while let Some(mut delta) = self.undo.pop_back() {
+            delta.swap(vm);
+            let edit = delta.edit;
+            self.redo.push(delta);
+            if !edit {
+                break;
+            }
+        }
+        self.undo_steps -= 1;
+        self.redo_steps += 1;
+        true
+    }
 
-        self.size -= n;
-        if self.ptr <= n {
-            self.ptr += self.buf.len();
+    /// 방금 undo_one으로 되돌린 instruction을 실행한 직후의 상태를 반환합니다. 기록은 바꾸지 않습니다.
+    pub fn state_after_undone(&self, vm: &VM) -> Option<VM> {
+        let mut delta = self.redo.last().filter(|delta| !delta.edit)?.clone();
+        let mut after = vm.clone();
+        delta.swap(&mut after);
+        Some(after)
+    }
+
+    /// undo_one으로 되돌린 instruction 하나와, 그 뒤에 set 등으로 바꾼 기록을 다시 적용합니다.
+    /// 되돌린 instruction이 없으면 false를 반환합니다.
+    pub fn redo_one(&mut self, vm: &mut VM) -> bool {
+        if self.redo_steps == 0 {
+            return false;
         }
-        self.ptr -= n;
-        Some(&self.buf[self.ptr])
+        // undo_edit로 따로 되돌린 기록은 instruction보다 먼저 다시 적용합니다.
+        while self.redo_edit(vm) {}
+        while let Some(mut delta) = self.redo.pop() {
+            delta.swap(vm);
+            self.undo.push_back(delta);
+            if self.redo.last().is_none_or(|next| !next.edit) {
+                break;
+            }
+        }
+        self.redo_steps -= 1;
+        self.undo_steps += 1;
+        true
+    }
+
+    /// redo로 다시 적용할 수 있는 instruction의 수
+    pub fn redo_len(&self) -> usize {
+        self.redo_steps
     }
 
-    pub fn undo_command(&mut self, vm: &mut VM, term: &Term, n: usize) -> IOResult<()> {
-        if let Some(uvm) = self.undo(n) {
-            *vm = uvm.clone();
+    /// 되돌린 instruction의 개수를 반환합니다. 되돌릴 수 없다면 None을 반환합니다.
+    /// 가장 최근의 기록이 set 등으로 바꾼 기록이라면 그것 하나만 되돌리고 Some(0)을 반환합니다.
+    pub fn undo_command(&mut self, vm: &mut VM, term: &Term, n: usize) -> IOResult<Option<usize>> {
+        if self.undo_edit(vm) {
+            term.write_line("set으로 바꾼 값을 되돌렸습니다.")?;
+            Ok(Some(0))
+        } else if n <= self.len() {
+            for _ in 0..n {
+                self.undo_one(vm);
+            }
             term.write_line(&format!(
                 "{} instruction 전으로 VM의 상태를 되돌렸습니다.",
                 n
             ))?;
+            Ok(Some(n))
         } else {
             term.write_line(&format!(
                 "되돌릴 수 없습니다. 최대 {} instruction만큼만 돌릴 수 있습니다.",
-                self.size
+                self.len()
+            ))?;
+            Ok(None)
+        }
+    }
+
+    /// 다시 적용한 instruction의 개수를 반환합니다. 다시 돌릴 수 없다면 None을 반환합니다.
+    /// 가장 최근에 되돌린 기록이 undo로 따로 되돌린 set이라면 그것 하나만 다시 적용하고 Some(0)을 반환합니다.
+    pub fn redo_command(&mut self, vm: &mut VM, term: &Term, n: usize) -> IOResult<Option<usize>> {
+        if self.redo_edit(vm) {
+            term.write_line("set으로 바꾼 값을 다시 적용했습니다.")?;
+            Ok(Some(0))
+        } else if n <= self.redo_len() {
+            for _ in 0..n {
+                self.redo_one(vm);
+            }
+            term.write_line(&format!(
+                "{} instruction 뒤로 VM의 상태를 다시 돌렸습니다.",
+                n
+            ))?;
+            Ok(Some(n))
+        } else {
+            term.write_line(&format!(
+                "다시 돌릴 수 없습니다. 최대 {} instruction만큼만 다시 돌릴 수 있습니다.",
+                self.redo_len()
             ))?;
+            Ok(None)
         }
-        Ok(())
+    }
+
+    /// stat에 보여줄, 기록 안에서의 현재 위치
+    pub fn print_position(&self, term: &Term) -> IOResult<()> {
+        term.write_line(&format!(
+            "기록: {}/{} (undo {}, redo {})",
+            self.len(),
+            self.len() + self.redo_len(),
+            self.len(),
+            self.redo_len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// instruction 하나를 실행한 것처럼 R0을 1 늘립니다.
+    fn step(buffer: &mut UndoBuffer, vm: &mut VM) {
+        buffer.push(vm);
+        vm.register[0] += 1;
+        vm.pc += 1;
+    }
+
+    /// set r1 value처럼 R1을 바꿉니다.
+    fn set_r1(buffer: &mut UndoBuffer, vm: &mut VM, value: i16) {
+        buffer.push_change(vm, &[]);
+        vm.register[1] = value;
+    }
+
+    #[test]
+    fn edits_are_not_counted_as_steps() {
+        let mut vm = VM::new();
+        let mut buffer = UndoBuffer::new(100);
+        step(&mut buffer, &mut vm);
+        set_r1(&mut buffer, &mut vm, 5);
+        set_r1(&mut buffer, &mut vm, 6);
+        step(&mut buffer, &mut vm);
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.redo_len(), 0);
+
+        assert!(buffer.undo_one(&mut vm));
+        assert_eq!((vm.register[0], vm.register[1]), (1, 6));
+        assert!(buffer.undo_one(&mut vm));
+        assert_eq!((vm.register[0], vm.register[1]), (0, 0));
+        assert!(!buffer.undo_one(&mut vm));
+        assert_eq!((buffer.len(), buffer.redo_len()), (0, 2));
+
+        assert!(buffer.redo_one(&mut vm));
+        assert_eq!((vm.register[0], vm.register[1]), (1, 6));
+        assert!(buffer.redo_one(&mut vm));
+        assert_eq!((vm.register[0], vm.register[1]), (2, 6));
+        assert!(!buffer.redo_one(&mut vm));
+        assert_eq!((buffer.len(), buffer.redo_len()), (2, 0));
+    }
+
+    #[test]
+    fn edits_are_undone_alone() {
+        let mut vm = VM::new();
+        let mut buffer = UndoBuffer::new(100);
+        set_r1(&mut buffer, &mut vm, 5);
+        assert!(buffer.is_empty());
+        assert!(buffer.undo_edit(&mut vm));
+        assert_eq!((vm.pc, vm.register[0], vm.register[1]), (0, 0, 0));
+        assert!(!buffer.undo_edit(&mut vm));
+        assert!(buffer.redo_edit(&mut vm));
+        assert_eq!(vm.register[1], 5);
+
+        step(&mut buffer, &mut vm);
+        set_r1(&mut buffer, &mut vm, 6);
+        assert!(buffer.undo_edit(&mut vm));
+        assert_eq!((vm.pc, vm.register[0], vm.register[1]), (1, 1, 5));
+        assert!(!buffer.undo_edit(&mut vm));
+        assert!(buffer.undo_one(&mut vm));
+        assert_eq!((vm.pc, vm.register[0], vm.register[1]), (0, 0, 5));
+
+        // 따로 되돌린 set은 instruction을 다시 적용할 때 함께 다시 적용됩니다.
+        assert!(buffer.redo_one(&mut vm));
+        assert_eq!((vm.pc, vm.register[0], vm.register[1]), (1, 1, 6));
+        assert_eq!((buffer.len(), buffer.redo_len()), (1, 0));
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut vm = VM::new();
+        let mut buffer = UndoBuffer::new(2);
+        step(&mut buffer, &mut vm);
+        set_r1(&mut buffer, &mut vm, 5);
+        step(&mut buffer, &mut vm);
+        assert_eq!(buffer.len(), 1);
+        step(&mut buffer, &mut vm);
+        assert_eq!(buffer.len(), 2);
     }
 }
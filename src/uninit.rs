@@ -0,0 +1,244 @@
+use crate::access::MemoryAccess;
+use crate::asm::Assembly;
+use crate::helper::{report_error, Status};
+use crate::symbol::{symbol_table_query, TableEntry};
+use console::Term;
+use lc3::vm::instruction::Instruction;
+use lc3::vm::VM;
+use std::collections::HashSet;
+use std::io::Result as IOResult;
+
+/// 사용자 프로그램이 쓸 수 있는 메모리의 시작 주소. 그 앞의 운영체제 코드는 검사하지 않습니다.
+const USER_SPACE: usize = 0x3000;
+/// 장치 레지스터 영역의 시작 주소
+const DEVICE_SPACE: usize = 0xFE00;
+
+/// 초기화되지 않은 값을 읽었을 때 어떻게 할지 정합니다.
+#[derive(Clone, Copy, PartialEq)]
+pub enum UninitMode {
+    /// 검사하지 않습니다.
+    Off,
+    /// 경고를 출력하고 계속 실행합니다. 같은 instruction에 대해서는 한 번만 경고합니다.
+    Warn,
+    /// 실행하지 않고 멈춥니다.
+    Stop,
+}
+
+/// instruction이 읽는 레지스터들
+fn read_registers(instruction: u16) -> Vec<usize> {
+    let regs: Vec<i16> = match Instruction::from_u16(instruction) {
+        Instruction::ADD { src1, src2, .. } | Instruction::AND { src1, src2, .. } => {
+            vec![src1, src2]
+        }
+        // AND Rn, Rn, #0은 레지스터를 0으로 만드는 관용구이므로 읽는 것으로 보지 않습니다.
+        Instruction::ANDi { immd: 0, .. } => vec![],
+        Instruction::ADDi { src, .. }
+        | Instruction::ANDi { src, .. }
+        | Instruction::NOT { src, .. }
+        | Instruction::ST { src, .. }
+        | Instruction::STI { src, .. } => vec![src],
+        Instruction::STR { src, base, .. } => vec![src, base],
+        Instruction::JMP { base } | Instruction::JSRR { base } | Instruction::LDR { base, .. } => {
+            vec![base]
+        }
+        // OUT, PUTS, PUTSP는 R0을 출력합니다.
+        Instruction::TRAP { vect } if vect == 0x21 || vect == 0x22 || vect == 0x24 => vec![0],
+        _ => vec![],
+    };
+    regs.into_iter().map(|r| r as usize).collect()
+}
+
+/// instruction이 쓰는 레지스터
+fn written_register(instruction: u16) -> Option<usize> {
+    match Instruction::from_u16(instruction) {
+        Instruction::ADD { dst, .. }
+        | Instruction::ADDi { dst, .. }
+        | Instruction::AND { dst, .. }
+        | Instruction::ANDi { dst, .. }
+        | Instruction::LD { dst, .. }
+        | Instruction::LDI { dst, .. }
+        | Instruction::LDR { dst, .. }
+        | Instruction::LEA { dst, .. }
+        | Instruction::NOT { dst, .. } => Some(dst as usize),
+        Instruction::JSR { .. } | Instruction::JSRR { .. } | Instruction::TRAP { .. } => Some(7),
+        _ => None,
+    }
+}
+
+/// 레지스터와 메모리 word마다 값이 정해졌는지(프로그램을 불러오거나 값을 쓴 적이 있는지) 기록합니다.
+pub struct UninitTracker {
+    registers: [bool; 8],
+    memory: Vec<bool>,
+    pub mode: UninitMode,
+    /// Warn 모드에서 이미 경고한 instruction의 주소
+    warned: HashSet<u16>,
+}
+
+impl Default for UninitTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UninitTracker {
+    /// 운영체제 영역과 장치 레지스터만 정해진 상태로 만듭니다.
+    pub fn new() -> UninitTracker {
+        let memory = (0..65536)
+            .map(|addr| !(USER_SPACE..DEVICE_SPACE).contains(&addr))
+            .collect();
+        UninitTracker {
+            registers: [false; 8],
+            memory,
+            mode: UninitMode::Off,
+            warned: HashSet::new(),
+        }
+    }
+
+    /// 오브젝트 파일(첫 word는 시작 주소)이 차지하는 메모리를 정해진 것으로 기록합니다.
+    pub fn load_object(&mut self, object: &[u8]) {
+        if object.len() < 2 {
+            return;
+        }
+        let orig = (usize::from(object[0]) << 8) + usize::from(object[1]);
+        let end = (orig + (object.len() - 2) / 2).min(self.memory.len());
+        for defined in self.memory[orig..end].iter_mut() {
+            *defined = true;
+        }
+    }
+
+    /// 어셈블 결과를 기록합니다. .BLKW로 잡아 둔 word는 정해지지 않은 것으로 봅니다.
+    pub fn load_assembly(&mut self, assembly: &Assembly) {
+        for section in assembly.sections.iter() {
+            let orig = section.orig as usize;
+            for defined in self.memory[orig..orig + section.words.len()].iter_mut() {
+                *defined = true;
+            }
+        }
+        for &addr in assembly.reserved.iter() {
+            self.memory[addr as usize] = false;
+        }
+    }
+
+    /// 모든 레지스터와 메모리를 정해진 것으로 봅니다. (snapshot load)
+    pub fn define_all(&mut self) {
+        self.registers = [true; 8];
+        for defined in self.memory.iter_mut() {
+            *defined = true;
+        }
+    }
+
+    /// set 등으로 레지스터의 값을 정했을 때 호출합니다.
+    pub fn define_register(&mut self, register: usize) {
+        self.registers[register] = true;
+    }
+
+    /// set 등으로 메모리의 값을 정했을 때 호출합니다.
+    pub fn define_memory(&mut self, addr: usize) {
+        self.memory[addr] = true;
+    }
+
+    /// PC의 instruction을 실행하기 전에 호출합니다.
+    /// 사용자 프로그램의 instruction이 정해지지 않은 레지스터나 메모리를 읽는다면, 그 목록을 설명하는 문자열을 반환합니다.
+    /// Warn 모드에서는 instruction마다 한 번만 반환합니다.
+    pub fn check(
+        &mut self,
+        vm: &VM,
+        access: &MemoryAccess,
+        symbol_table: &[TableEntry],
+    ) -> Option<String> {
+        let pc = vm.pc as usize;
+        if self.mode == UninitMode::Off || pc < USER_SPACE {
+            return None;
+        }
+        let mut undefined = read_registers(vm.mem[pc])
+            .into_iter()
+            .filter(|&r| !self.registers[r])
+            .map(|r| format!("R{}", r))
+            .collect::<Vec<_>>();
+        undefined.extend(
+            access
+                .reads
+                .iter()
+                .filter(|&&addr| !self.memory[addr])
+                .map(|&addr| {
+                    format!(
+                        "mem[x{:04X}] ({})",
+                        addr,
+                        symbol_table_query(symbol_table, addr)
+                    )
+                }),
+        );
+        if undefined.is_empty() || (self.mode == UninitMode::Warn && !self.warned.insert(vm.pc)) {
+            return None;
+        }
+        Some(format!(
+            "x{:04X} ({}) {}: 초기화되지 않은 값을 읽습니다: {}",
+            pc,
+            symbol_table_query(symbol_table, pc),
+            Instruction::from_u16(vm.mem[pc]),
+            undefined.join(", ")
+        ))
+    }
+
+    /// PC의 instruction을 실행하기 전에 호출해, 실행으로 값이 정해질 레지스터와 메모리를 기록합니다.
+    /// 사용자 프로그램이 쓴 값은 항상 정해진 것으로 보고(경고가 이어지지 않도록),
+    /// 운영체제의 코드는 읽은 값이 정해졌는지를 그대로 옮깁니다. (레지스터를 저장했다 되돌리는 경우)
+    pub fn update(&mut self, vm: &VM, access: &MemoryAccess) {
+        let pc = vm.pc as usize;
+        let instruction = vm.mem[pc];
+        if pc >= USER_SPACE {
+            if let Some(dst) = written_register(instruction) {
+                self.registers[dst] = true;
+            }
+            if let Some(addr) = access.write {
+                self.memory[addr] = true;
+            }
+            return;
+        }
+
+        let reads_defined = read_registers(instruction)
+            .into_iter()
+            .all(|r| self.registers[r]);
+        match Instruction::from_u16(instruction) {
+            Instruction::LD { dst, .. }
+            | Instruction::LDI { dst, .. }
+            | Instruction::LDR { dst, .. } => {
+                // LDI는 마지막으로 읽는 주소의 값이 레지스터에 들어갑니다.
+                self.registers[dst as usize] =
+                    access.reads.last().is_none_or(|&addr| self.memory[addr]);
+            }
+            Instruction::ST { src, .. }
+            | Instruction::STI { src, .. }
+            | Instruction::STR { src, .. } => {
+                if let Some(addr) = access.write.filter(|&addr| addr < DEVICE_SPACE) {
+                    self.memory[addr] = self.registers[src as usize];
+                }
+            }
+            _ => {
+                if let Some(dst) = written_register(instruction) {
+                    self.registers[dst] = dst == 7 || reads_defined;
+                }
+            }
+        }
+    }
+
+    /// uninit [off|warn|stop]: 초기화되지 않은 값을 읽는지 검사하는 방법을 정하고, 현재 상태를 출력합니다.
+    pub fn uninit_command(&mut self, term: &Term, body: Option<&str>) -> IOResult<Status> {
+        match body.map(str::trim) {
+            None | Some("") => (),
+            Some("off") => self.mode = UninitMode::Off,
+            Some("warn") => {
+                self.mode = UninitMode::Warn;
+                self.warned.clear();
+            }
+            Some("stop") => self.mode = UninitMode::Stop,
+            Some(_) => return report_error(term, "사용법: uninit [off|warn|stop]"),
+        }
+        term.write_line(match self.mode {
+            UninitMode::Off => "초기화되지 않은 값을 읽는지 검사하지 않습니다.",
+            UninitMode::Warn => "초기화되지 않은 값을 읽으면 경고합니다.",
+            UninitMode::Stop => "초기화되지 않은 값을 읽으면 실행을 멈춥니다.",
+        })?;
+        Ok(Status::Done)
+    }
+}